@@ -39,11 +39,20 @@ pub trait ArchiveFilter {
     fn archive_filter(&self, item: &str) -> bool;
 }
 
+/// Size and SHA-256 of an archived file, computed without keeping its
+/// content.
+#[derive(Clone, Debug)]
+pub struct FileDigest {
+    pub size: u64,
+    pub sha256: String,
+}
+
 pub trait Archive {
     type This;
     async fn new<P: AsRef<Path>>(file: P) -> std::io::Result<Self::This>;
     fn enumerate(&self, filter: impl ArchiveFilter) -> Vec<&str>;
     fn reader(&mut self, filename: &str) -> tokio::io::Result<String>;
+    fn digest(&mut self, filename: &str) -> tokio::io::Result<FileDigest>;
 }
 pub enum SupportedExtension {
     Zip,
@@ -4,10 +4,12 @@ use std::path::Path;
 
 use std::fs::File;
 
+use sha2::{Digest, Sha256};
 use zip::ZipArchive;
 
 use super::ArchiveFilter;
 use super::Archive;
+use super::FileDigest;
 
 #[derive(Debug)]
 pub struct Zarchive {
@@ -43,6 +45,24 @@ impl Archive for Zarchive {
 
         Ok(content.to_string())
     }
+
+    fn digest(&mut self, filename: &str) -> tokio::io::Result<FileDigest> {
+        let mut z_file = self.archive.by_name(filename)?;
+        let mut hash = Sha256::new();
+        let mut buff = [0; 8192];
+
+        loop {
+            let bytes = z_file.read(&mut buff)?;
+            if bytes == 0 {
+                break;
+            }
+            hash.update(&buff[..bytes]);
+        }
+
+        let sha256 = hash.finalize().iter().map(|byte| format!("{:02x}", byte)).collect();
+
+        Ok(FileDigest { size: z_file.size(), sha256 })
+    }
     
 
 }
\ No newline at end of file
@@ -0,0 +1,180 @@
+use std::{collections::HashMap, fs::File, io::BufReader, io::Result, path::Path};
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+const SETTINGS_FILE: &str = "settings.json";
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GeoIpSettings {
+    pub city_database: Option<String>,
+    pub asn_database: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StixSettings {
+    /// Name of the identity the bundle is created by.
+    pub identity: Option<String>,
+    /// Only logs infected within this inclusive range are exported.
+    pub date_from: Option<NaiveDate>,
+    pub date_to: Option<NaiveDate>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MispSettings {
+    /// TLP level tagged on every event, `amber` when unset.
+    pub tlp: Option<String>,
+    /// Additional tags added to every event.
+    pub tags: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CredentialSettings {
+    /// Extra field names per credential field (`url`, `username`, `password`,
+    /// `application`), added to the built-in aliases.
+    pub aliases: HashMap<String, Vec<String>>,
+    /// Owner domain of Android apps we monitor, keyed by package name
+    /// (`com.example.app`).
+    pub android_owners: HashMap<String, String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrivacyAction {
+    #[default]
+    Keep,
+    /// Keeps only the first and last character.
+    Mask,
+    /// Replaces the value with its HMAC-SHA256 under `PrivacySettings::key`.
+    Hmac,
+    Drop,
+}
+
+/// Passwords and cookie values are dropped unless the policy says otherwise,
+/// so nothing secret is indexed in plaintext without an explicit choice.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrivacySettings {
+    /// Name recorded on every indexed document, `default` when unset.
+    pub name: Option<String>,
+    /// Secret used by the `hmac` action.
+    pub key: Option<String>,
+    pub password: PrivacyAction,
+    pub username: PrivacyAction,
+    pub cookie_value: PrivacyAction,
+    pub ip: PrivacyAction,
+    pub hwid: PrivacyAction,
+    /// Values saved in browser autofill.
+    pub autofill_value: PrivacyAction,
+}
+
+impl Default for PrivacySettings {
+    fn default() -> Self {
+        Self {
+            name: None,
+            key: None,
+            password: PrivacyAction::Drop,
+            username: PrivacyAction::Keep,
+            cookie_value: PrivacyAction::Drop,
+            ip: PrivacyAction::Keep,
+            hwid: PrivacyAction::Keep,
+            autofill_value: PrivacyAction::Mask,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExposureAlgorithm {
+    /// Plain SHA-1, so clients can compute range prefixes themselves.
+    Sha1,
+    /// HMAC-SHA1 under `ExposureSettings::key`; only this tool can compute it.
+    Hmac,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExposureSettings {
+    /// `hmac` when unset; without a key exposure records are then not
+    /// written at all.
+    pub algorithm: Option<ExposureAlgorithm>,
+    pub key: Option<String>,
+    /// Address of the range endpoint, `127.0.0.1:8787` when unset.
+    pub listen: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DirectorySettings {
+    /// Offline export of our directory's password hashes (`user:hash`
+    /// lines, NTLM or bcrypt); the check is disabled when unset.
+    pub export: Option<String>,
+    /// Domains of our organisation, matched against email and site domains.
+    pub domains: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CookieMode {
+    #[default]
+    Full,
+    /// Cookie values are replaced by their HMAC under `PrivacySettings::key`
+    /// and never indexed.
+    Metadata,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuthCookieService {
+    pub service: String,
+    /// Domains the service sets its cookies on; subdomains match too.
+    pub domains: Vec<String>,
+    /// Names of the cookies that carry an authenticated session. A trailing
+    /// `*` matches by prefix (`wordpress_logged_in_*`).
+    pub cookies: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CookieSettings {
+    /// `--cookie-metadata` selects `metadata` for a single run.
+    pub mode: CookieMode,
+    /// Catalogue of monitored services used for session exposure scoring.
+    pub auth_cookies: Vec<AuthCookieService>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub geoip: GeoIpSettings,
+    pub credentials: CredentialSettings,
+    pub cookies: CookieSettings,
+    pub stix: StixSettings,
+    pub misp: MispSettings,
+    pub privacy: PrivacySettings,
+    pub exposure: ExposureSettings,
+    pub directory: DirectorySettings,
+}
+
+impl Settings {
+    pub fn load() -> Result<Self> {
+        Self::load_from(SETTINGS_FILE)
+    }
+
+    pub fn load_from<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let filename = path.as_ref();
+
+        if !filename.exists() {
+            return Ok(Self::default());
+        };
+
+        let reader = BufReader::new(File::open(filename)?);
+
+        serde_json::from_reader(reader)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err.to_string()))
+    }
+}
@@ -0,0 +1,252 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fs::File,
+    io::{BufRead, BufReader, Result},
+    path::Path,
+};
+
+use chrono::{DateTime, Utc};
+use md4::{Digest, Md4};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::DirectorySettings,
+    log_processor::{
+        identity::IdentityKind, info_log_processor::LogInfo, pass_log_processor::Credential,
+    },
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum DirectoryHash {
+    Ntlm(String),
+    Bcrypt(String),
+}
+
+impl DirectoryHash {
+    fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+
+        if value.starts_with("$2") {
+            return Some(Self::Bcrypt(value.to_string()));
+        }
+        if value.len() == 32 && value.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Some(Self::Ntlm(value.to_lowercase()));
+        }
+
+        None
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Ntlm(_) => "ntlm",
+            Self::Bcrypt(_) => "bcrypt",
+        }
+    }
+
+    fn matches(&self, password: &str) -> bool {
+        match self {
+            Self::Ntlm(hash) => *hash == ntlm_hash(password),
+            Self::Bcrypt(hash) => bcrypt::verify(password, hash).unwrap_or(false),
+        }
+    }
+}
+
+fn ntlm_hash(password: &str) -> String {
+    let utf16 = password
+        .encode_utf16()
+        .flat_map(|unit| unit.to_le_bytes())
+        .collect::<Vec<_>>();
+
+    Md4::digest(utf16)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// `CORP\jdoe`, `jdoe@corp.com` and `JDoe` all name the account `jdoe`.
+fn account_name(value: &str) -> String {
+    let value = value.trim().to_lowercase();
+    let value = value.rsplit('\\').next().unwrap_or_default();
+
+    value.split('@').next().unwrap_or_default().to_string()
+}
+
+/// Password hashes of our own directory, keyed by account name. Lines are
+/// either `user:hash` (NTLM hex or bcrypt) or secretsdump
+/// `DOMAIN\user:rid:lmhash:nthash:::` entries.
+#[derive(Debug, Default)]
+pub struct DirectoryExport {
+    accounts: HashMap<String, DirectoryHash>,
+}
+
+impl DirectoryExport {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut export = Self::default();
+        let mut skipped = 0;
+
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            match Self::parse_line(line) {
+                Some((account, hash)) => {
+                    export.accounts.insert(account, hash);
+                }
+                None => skipped += 1,
+            }
+        }
+
+        if skipped > 0 {
+            eprintln!("[-] Directory export: {} unreadable lines skipped", skipped);
+        }
+
+        Ok(export)
+    }
+
+    fn parse_line(line: &str) -> Option<(String, DirectoryHash)> {
+        let fields = line.split(':').collect::<Vec<_>>();
+
+        let (account, hash) = match fields.len() {
+            n if n >= 4 && !fields[0].is_empty() && fields[1].chars().all(|c| c.is_ascii_digit()) => {
+                (fields[0], DirectoryHash::parse(fields[3])?)
+            }
+            _ => {
+                let (account, hash) = line.split_once(':')?;
+                (account, DirectoryHash::parse(hash)?)
+            }
+        };
+
+        let account = account_name(account);
+
+        if account.is_empty() {
+            return None;
+        }
+
+        Some((account, hash))
+    }
+
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+}
+
+/// Where a directory account's password leaked.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct DirectorySighting {
+    domain: Option<String>,
+    hwid: Option<String>,
+    infection_date: Option<DateTime<Utc>>,
+}
+
+/// A directory account whose current password was found in the logs. Only
+/// the account and where it leaked are reported, never the password.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectoryMatch {
+    account: String,
+    hash_type: String,
+    sightings: BTreeSet<DirectorySighting>,
+}
+
+/// Matches of a run, one entry per account however many logs and sites
+/// its password was found in.
+#[derive(Debug, Default)]
+pub struct DirectoryReport {
+    accounts: BTreeMap<String, DirectoryMatch>,
+}
+
+impl DirectoryReport {
+    pub fn add(&mut self, found: DirectoryMatch) {
+        match self.accounts.get_mut(&found.account) {
+            Some(known) => known.sightings.extend(found.sightings),
+            None => {
+                self.accounts.insert(found.account.clone(), found);
+            }
+        }
+    }
+
+    pub fn matches(&self) -> Vec<&DirectoryMatch> {
+        self.accounts.values().collect()
+    }
+}
+
+pub struct DirectoryChecker {
+    export: DirectoryExport,
+    domains: HashSet<String>,
+}
+
+impl DirectoryChecker {
+    /// `None` when no export is configured.
+    pub fn new(settings: &DirectorySettings) -> Result<Option<Self>> {
+        let Some(path) = &settings.export else {
+            return Ok(None);
+        };
+
+        Ok(Some(Self::with_export(
+            DirectoryExport::load(path)?,
+            &settings.domains,
+        )))
+    }
+
+    pub fn with_export(export: DirectoryExport, domains: &[String]) -> Self {
+        Self {
+            export,
+            domains: domains
+                .iter()
+                .map(|domain| domain.trim().to_lowercase())
+                .collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.export.len()
+    }
+
+    /// Credentials count as ours when their email domain or the site they
+    /// were saved for is one of the configured domains.
+    fn is_ours(&self, credential: &Credential) -> bool {
+        let email_domain = credential
+            .identity()
+            .filter(|identity| identity.kind() == IdentityKind::Email)
+            .and_then(|identity| identity.email_domain());
+
+        [email_domain, credential.domain()]
+            .iter()
+            .flatten()
+            .any(|domain| {
+                self.domains.iter().any(|ours| {
+                    domain == ours || domain.ends_with(&format!(".{}", ours))
+                })
+            })
+    }
+
+    /// bcrypt verification is slow by design; call this from a blocking
+    /// task, not from the async runtime.
+    pub fn check(&self, credential: &Credential, info: &LogInfo) -> Option<DirectoryMatch> {
+        if !self.is_ours(credential) {
+            return None;
+        }
+
+        let account = account_name(&credential.username()?);
+        let password = credential.password()?;
+        let hash = self.export.accounts.get(&account)?;
+
+        if !hash.matches(&password) {
+            return None;
+        }
+
+        Some(DirectoryMatch {
+            account,
+            hash_type: hash.name().to_string(),
+            sightings: BTreeSet::from([DirectorySighting {
+                domain: credential.domain(),
+                hwid: info.hwid(),
+                infection_date: info.infection_date(),
+            }]),
+        })
+    }
+}
@@ -1,12 +1,12 @@
 use std::{
     borrow::Borrow,
-    io::{Error, Result},
+    io::{Error, ErrorKind, Result},
 };
 
 use elasticsearch::{
     http::transport::Transport,
     indices::{IndicesCreateParts, IndicesExistsParts},
-    BulkIndexOperation, BulkOperation, BulkParts, Elasticsearch, IndexParts,
+    BulkIndexOperation, BulkOperation, BulkParts, Elasticsearch, IndexParts, SearchParts,
 };
 use serde::{Serialize, Serializer};
 use serde_json::{json, Value};
@@ -90,6 +90,7 @@ impl ElasticsearchClient {
             client
                 .indices()
                 .create(IndicesCreateParts::Index(&mapping.name))
+                .body(mapping.mapping())
                 .send()
                 .await
                 .unwrap();
@@ -98,6 +99,26 @@ impl ElasticsearchClient {
         Ok(())
     }
 
+    pub async fn search(&self, index_name: &str, body: Value) -> Result<Value> {
+        let client = self.client.clone();
+
+        let response = client
+            .search(SearchParts::Index(&[index_name]))
+            .body(body)
+            .send()
+            .await
+            .map_err(|err| Error::other(err.to_string()))?;
+
+        if !response.status_code().is_success() {
+            return Err(Error::other(response.status_code().as_str()));
+        }
+
+        response
+            .json::<Value>()
+            .await
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))
+    }
+
     pub async fn insert_many<R: Serialize, D: IntoIterator<Item = R>>(
         &self,
         index_name: &str,
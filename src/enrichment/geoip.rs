@@ -0,0 +1,85 @@
+use std::{io::Result, net::IpAddr};
+
+use maxminddb::{geoip2, Reader};
+
+use crate::{
+    config::GeoIpSettings,
+    log_processor::info_log_processor::{GeoInfo, LogInfo, LogInfoFields},
+};
+
+fn open_database(path: &Option<String>) -> Result<Option<Reader<Vec<u8>>>> {
+    let Some(path) = path else {
+        return Ok(None);
+    };
+
+    Reader::open_readfile(path)
+        .map(Some)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err.to_string()))
+}
+
+/// Looks up the victim IP reported in the info file against local
+/// MaxMind-format databases. Either database may be absent, in which case
+/// the matching fields are simply left empty.
+pub struct GeoIpEnricher {
+    city: Option<Reader<Vec<u8>>>,
+    asn: Option<Reader<Vec<u8>>>,
+}
+
+impl GeoIpEnricher {
+    pub fn new(settings: &GeoIpSettings) -> Result<Self> {
+        Ok(Self {
+            city: open_database(&settings.city_database)?,
+            asn: open_database(&settings.asn_database)?,
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.city.is_some() || self.asn.is_some()
+    }
+
+    pub fn lookup(&self, address: IpAddr) -> Option<GeoInfo> {
+        let mut geo = GeoInfo::default();
+
+        if let Some(reader) = &self.city {
+            if let Ok(city) = reader.lookup::<geoip2::City>(address) {
+                geo.country_code = city
+                    .country
+                    .and_then(|country| country.iso_code)
+                    .map(|code| code.to_string());
+                geo.city = city
+                    .city
+                    .and_then(|city| city.names)
+                    .and_then(|names| names.get("en").map(|name| name.to_string()));
+            }
+        }
+
+        if let Some(reader) = &self.asn {
+            if let Ok(asn) = reader.lookup::<geoip2::Asn>(address) {
+                geo.asn = asn.autonomous_system_number;
+                geo.organization = asn
+                    .autonomous_system_organization
+                    .map(|organization| organization.to_string());
+            }
+        }
+
+        if geo.is_empty() {
+            None
+        } else {
+            Some(geo)
+        }
+    }
+
+    pub fn enrich(&self, mut info: LogInfo) -> LogInfo {
+        if !self.is_enabled() {
+            return info;
+        }
+
+        let address = info.ip().and_then(|ip| ip.parse::<IpAddr>().ok());
+
+        if let Some(geo) = address.and_then(|address| self.lookup(address)) {
+            info.set(LogInfoFields::Geo(geo));
+        }
+
+        info
+    }
+}
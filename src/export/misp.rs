@@ -0,0 +1,126 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::Utc;
+use serde_json::{json, Value};
+
+use crate::config::MispSettings;
+
+use super::{deterministic_uuid, log_country, redact_user_profile, ArchiveIntel};
+
+const DEFAULT_TLP: &str = "amber";
+
+fn attribute(event: &str, category: &str, kind: &str, value: &str, comment: String) -> Value {
+    json!({
+        "uuid": deterministic_uuid(&format!("{}:{}:{}", event, kind, value)),
+        "category": category,
+        "type": kind,
+        "value": value,
+        "comment": comment,
+        "to_ids": false,
+        "distribution": "5"
+    })
+}
+
+pub struct MispExporter {
+    tlp: String,
+    tags: Vec<String>,
+    domains: Vec<String>,
+}
+
+impl MispExporter {
+    /// `domains` are the organisation domains (`DirectorySettings::domains`);
+    /// only credential domains under them are exported.
+    pub fn new(settings: &MispSettings, domains: &[String]) -> Self {
+        Self {
+            tlp: settings
+                .tlp
+                .clone()
+                .unwrap_or(String::from(DEFAULT_TLP))
+                .to_lowercase(),
+            tags: settings.tags.clone(),
+            domains: domains
+                .iter()
+                .map(|domain| domain.trim().to_lowercase())
+                .collect(),
+        }
+    }
+
+    fn is_ours(&self, domain: &str) -> bool {
+        self.domains
+            .iter()
+            .any(|ours| domain == ours || domain.ends_with(&format!(".{}", ours)))
+    }
+
+    pub fn file_name(&self, intel: &ArchiveIntel) -> String {
+        format!("{}_misp.json", intel.archive())
+    }
+
+    /// Builds a MISP event for the archive. Attributes describe the campaign
+    /// (families, build ids, malware file paths) and its reach (victim
+    /// countries, our organisation's domains with exposed credentials); usernames,
+    /// passwords and cookie values are never read.
+    pub fn event(&self, intel: &ArchiveIntel) -> Value {
+        let event_uuid = deterministic_uuid(&format!("misp-event:{}", intel.archive()));
+        let mut families = BTreeSet::new();
+        let mut build_ids = BTreeMap::<String, usize>::new();
+        let mut paths = BTreeMap::<String, usize>::new();
+        let mut countries = BTreeMap::<String, usize>::new();
+
+        intel.logs().iter().for_each(|info| {
+            if let Some(family) = info.family() {
+                families.insert(family);
+            }
+            if let Some(build_id) = info.build_id() {
+                *build_ids.entry(build_id).or_default() += 1;
+            }
+            if let Some(path) = info.malware_path() {
+                *paths.entry(redact_user_profile(&path)).or_default() += 1;
+            }
+            if let Some(country) = log_country(info) {
+                *countries.entry(country).or_default() += 1;
+            }
+        });
+
+        let mut attributes = Vec::new();
+
+        families.iter().for_each(|family| {
+            attributes.push(attribute(&event_uuid, "Attribution", "text", family, String::from("malware family")));
+        });
+        build_ids.iter().for_each(|(build_id, count)| {
+            attributes.push(attribute(&event_uuid, "Attribution", "campaign-id", build_id, format!("build id seen in {} logs", count)));
+        });
+        paths.iter().for_each(|(path, count)| {
+            attributes.push(attribute(&event_uuid, "Payload installation", "filename", path, format!("malware path seen in {} logs", count)));
+        });
+        countries.iter().for_each(|(country, count)| {
+            attributes.push(attribute(&event_uuid, "Targeting data", "target-location", country, format!("{} victims", count)));
+        });
+        intel
+            .domains()
+            .iter()
+            .filter(|(domain, _)| self.is_ours(domain))
+            .for_each(|(domain, count)| {
+                attributes.push(attribute(&event_uuid, "Network activity", "domain", domain, format!("{} exposed credentials", count)));
+            });
+
+        let mut tags = vec![json!({"name": format!("tlp:{}", self.tlp)})];
+        families.iter().for_each(|family| {
+            tags.push(json!({"name": format!("misp-galaxy:stealer=\"{}\"", family)}));
+        });
+        self.tags.iter().for_each(|tag| tags.push(json!({"name": tag})));
+
+        json!({
+            "Event": {
+                "uuid": event_uuid,
+                "info": format!("Infostealer log archive {}", intel.archive()),
+                "date": Utc::now().date_naive().to_string(),
+                "threat_level_id": "2",
+                "analysis": "2",
+                "distribution": "0",
+                "published": false,
+                "Tag": tags,
+                "Attribute": attributes
+            }
+        })
+    }
+}
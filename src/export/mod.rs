@@ -0,0 +1,137 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{BufWriter, Result, Write},
+    path::{Path, PathBuf},
+};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Serialize;
+use sha1::{Digest, Sha1};
+
+use crate::log_processor::info_log_processor::LogInfo;
+
+pub mod misp;
+pub mod stix;
+
+lazy_static! {
+    static ref USER_PROFILE_REGEX: Regex = Regex::new(r"(?i)([\\/](?:users|home)[\\/])[^\\/]+").unwrap();
+}
+
+const EXPORT_DIRECTORY: &str = "exports";
+
+/// Name-based uuid (version 3 layout over MD5) so exported objects keep the
+/// same identifier when an archive is exported again.
+pub(crate) fn deterministic_uuid(key: &str) -> String {
+    let mut digest = md5::compute(key).0;
+    digest[6] = (digest[6] & 0x0f) | 0x30;
+    digest[8] = (digest[8] & 0x3f) | 0x80;
+
+    format_uuid(&digest)
+}
+
+/// RFC 4122 version 5 uuid (SHA-1) of `name` within `namespace`.
+pub(crate) fn uuid_v5(namespace: &[u8; 16], name: &str) -> String {
+    let digest = Sha1::new().chain_update(namespace).chain_update(name.as_bytes()).finalize();
+
+    let mut bytes = [0; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    bytes[6] = (bytes[6] & 0x0f) | 0x50;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    format_uuid(&bytes)
+}
+
+fn format_uuid(bytes: &[u8; 16]) -> String {
+    let hex = bytes.iter().map(|byte| format!("{:02x}", byte)).collect::<String>();
+
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+/// Replaces the profile folder in `C:\Users\<name>\...` so victim account
+/// names never leave in shared intel.
+pub(crate) fn redact_user_profile(path: &str) -> String {
+    USER_PROFILE_REGEX.replace_all(path, "${1}*").to_string()
+}
+
+/// ISO country of a log, preferring the GeoIP lookup over the self-reported
+/// value.
+pub(crate) fn log_country(info: &LogInfo) -> Option<String> {
+    info.geo()
+        .and_then(|geo| geo.country_code)
+        .or_else(|| info.country())
+        .filter(|country| country.len() == 2 && country.chars().all(|c| c.is_ascii_uppercase()))
+}
+
+/// Per-archive view of the parsed logs that intel exporters build on.
+#[derive(Clone, Debug)]
+pub struct ArchiveIntel {
+    archive: String,
+    logs: Vec<LogInfo>,
+    domains: BTreeMap<String, usize>,
+}
+
+impl ArchiveIntel {
+    pub fn new<A: AsRef<str>>(archive: A) -> Self {
+        Self {
+            archive: archive.as_ref().to_string(),
+            logs: Vec::new(),
+            domains: BTreeMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, info: LogInfo) {
+        self.logs.push(info)
+    }
+
+    /// Merges per-domain counts of exposed credentials.
+    pub fn add_domains<D: IntoIterator<Item = (String, usize)>>(&mut self, domains: D) {
+        domains.into_iter().for_each(|(domain, count)| {
+            *self.domains.entry(domain).or_default() += count;
+        });
+    }
+
+    pub fn archive(&self) -> &str {
+        &self.archive
+    }
+
+    pub fn logs(&self) -> &[LogInfo] {
+        &self.logs
+    }
+
+    pub fn domains(&self) -> &BTreeMap<String, usize> {
+        &self.domains
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.logs.is_empty()
+    }
+}
+
+pub struct ExportUtils;
+
+impl ExportUtils {
+    /// Writes `value` as pretty printed JSON to `exports/<name>`, creating the
+    /// directory on first use, and returns the written path.
+    pub fn write_json<N: AsRef<str>, T: Serialize>(name: N, value: &T) -> Result<PathBuf> {
+        let directory = Path::new(EXPORT_DIRECTORY);
+        fs::create_dir_all(directory)?;
+
+        let path = directory.join(name.as_ref());
+        let mut writer = BufWriter::new(File::create(&path)?);
+
+        serde_json::to_writer_pretty(&mut writer, value)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err.to_string()))?;
+        writer.flush()?;
+
+        Ok(path)
+    }
+}
@@ -0,0 +1,312 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
+use serde_json::{json, Value};
+
+use crate::{config::StixSettings, log_processor::info_log_processor::LogInfo};
+
+use super::{deterministic_uuid, log_country, redact_user_profile, uuid_v5, ArchiveIntel};
+
+const DEFAULT_IDENTITY: &str = "log-archive-processor";
+// 00abedb4-aa42-466c-9c01-fed23315a9b7, the namespace STIX 2.1 mandates for
+// cyber-observable identifiers.
+const SCO_NAMESPACE: [u8; 16] = [
+    0x00, 0xab, 0xed, 0xb4, 0xaa, 0x42, 0x46, 0x6c, 0x9c, 0x01, 0xfe, 0xd2, 0x33, 0x15, 0xa9, 0xb7,
+];
+
+/// STIX identifiers are `<type>--<uuid>` with a uuid derived from the
+/// object key, so re-exporting the same archive yields the same object ids.
+pub(crate) fn stix_id(kind: &str, key: &str) -> String {
+    format!("{}--{}", kind, deterministic_uuid(&format!("{}:{}", kind, key)))
+}
+
+/// Cyber-observable ids are UUIDv5 over the serialized id contributing
+/// properties, as the specification requires, so partners derive the same id
+/// for the same file or directory and deduplicate them.
+fn sco_id(kind: &str, properties: Value) -> String {
+    format!("{}--{}", kind, uuid_v5(&SCO_NAMESPACE, &properties.to_string()))
+}
+
+fn timestamp(date: DateTime<Utc>) -> String {
+    date.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+fn escape_pattern(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+#[derive(Default)]
+struct Activity {
+    logs: usize,
+    countries: BTreeSet<String>,
+    first_seen: Option<DateTime<Utc>>,
+    last_seen: Option<DateTime<Utc>>,
+}
+
+impl Activity {
+    fn observe(&mut self, info: &LogInfo) {
+        self.logs += 1;
+
+        if let Some(country) = log_country(info) {
+            self.countries.insert(country);
+        }
+
+        if let Some(date) = info.infection_date() {
+            self.first_seen = Some(self.first_seen.map_or(date, |seen| seen.min(date)));
+            self.last_seen = Some(self.last_seen.map_or(date, |seen| seen.max(date)));
+        }
+    }
+}
+
+#[derive(Default)]
+struct FamilyActivity {
+    activity: Activity,
+    build_ids: BTreeSet<String>,
+    // file name -> (redacted parent directory, activity)
+    files: BTreeMap<String, (Option<String>, Activity)>,
+}
+
+pub struct StixExporter {
+    identity: String,
+    date_from: Option<NaiveDate>,
+    date_to: Option<NaiveDate>,
+}
+
+impl StixExporter {
+    pub fn new(settings: &StixSettings) -> Self {
+        Self {
+            identity: settings
+                .identity
+                .clone()
+                .unwrap_or(String::from(DEFAULT_IDENTITY)),
+            date_from: settings.date_from,
+            date_to: settings.date_to,
+        }
+    }
+
+    pub fn file_name(&self, intel: &ArchiveIntel) -> String {
+        match (self.date_from, self.date_to) {
+            (None, None) => format!("{}_stix.json", intel.archive()),
+            (from, to) => format!(
+                "{}_stix_{}_{}.json",
+                intel.archive(),
+                from.map(|date| date.to_string()).unwrap_or_default(),
+                to.map(|date| date.to_string()).unwrap_or_default()
+            ),
+        }
+    }
+
+    fn in_range(&self, info: &LogInfo) -> bool {
+        if self.date_from.is_none() && self.date_to.is_none() {
+            return true;
+        }
+
+        let Some(date) = info.infection_date().map(|date| date.date_naive()) else {
+            return false;
+        };
+
+        self.date_from.is_none_or(|from| date >= from) && self.date_to.is_none_or(|to| date <= to)
+    }
+
+    fn collect(&self, intel: &ArchiveIntel) -> BTreeMap<String, FamilyActivity> {
+        let mut families: BTreeMap<String, FamilyActivity> = BTreeMap::new();
+
+        // Logs without a detected family have no malware object to hang
+        // their indicators on.
+        intel.logs().iter().filter(|info| self.in_range(info)).for_each(|info| {
+            let Some(family) = info.family() else {
+                return;
+            };
+            let entry = families.entry(family).or_default();
+
+            entry.activity.observe(info);
+
+            if let Some(build_id) = info.build_id() {
+                entry.build_ids.insert(build_id);
+            }
+
+            if let Some(path) = info.malware_path() {
+                let path = redact_user_profile(&path);
+                let (directory, name) = match path.rfind(['\\', '/']) {
+                    Some(index) => (Some(path[..index].to_string()), path[index + 1..].to_string()),
+                    None => (None, path.clone()),
+                };
+
+                if !name.is_empty() {
+                    let file = entry.files.entry(name).or_insert((directory, Activity::default()));
+                    file.1.observe(info);
+                }
+            }
+        });
+
+        families
+    }
+
+    /// Builds a STIX 2.1 bundle of the archive's malware families, file
+    /// indicators, observed data and country sightings. Only campaign data is
+    /// read; no victim credential, cookie, IP or HWID is ever included.
+    pub fn bundle(&self, intel: &ArchiveIntel) -> Value {
+        let now = timestamp(Utc::now());
+        let identity_id = stix_id("identity", &self.identity);
+        let mut objects = vec![json!({
+            "type": "identity",
+            "spec_version": "2.1",
+            "id": identity_id,
+            "created": now,
+            "modified": now,
+            "name": self.identity,
+            "identity_class": "organization"
+        })];
+        let mut locations = BTreeSet::new();
+        let mut observables = BTreeSet::new();
+
+        for (family, activity) in self.collect(intel) {
+            let malware_id = stix_id("malware", &family);
+            let first_seen = activity.activity.first_seen.map(timestamp).unwrap_or(now.clone());
+            let last_seen = activity.activity.last_seen.map(timestamp).unwrap_or(now.clone());
+
+            let mut malware = json!({
+                "type": "malware",
+                "spec_version": "2.1",
+                "id": malware_id,
+                "created_by_ref": identity_id,
+                "created": now,
+                "modified": now,
+                "name": family,
+                "is_family": true,
+                "malware_types": ["spyware"],
+                "first_seen": first_seen,
+                "last_seen": last_seen
+            });
+
+            if !activity.build_ids.is_empty() {
+                malware["x_build_ids"] = json!(activity.build_ids);
+            }
+            objects.push(malware);
+
+            let mut observed_refs = Vec::new();
+
+            for (name, (directory, file_activity)) in &activity.files {
+                let directory_id = directory.as_ref().map(|path| sco_id("directory", json!({"path": path})));
+                let file_id = match &directory_id {
+                    Some(id) => sco_id("file", json!({"name": name, "parent_directory_ref": id})),
+                    None => sco_id("file", json!({"name": name})),
+                };
+
+                if let (Some(path), Some(id)) = (directory, &directory_id) {
+                    if observables.insert(id.clone()) {
+                        objects.push(json!({
+                            "type": "directory",
+                            "spec_version": "2.1",
+                            "id": id,
+                            "path": path
+                        }));
+                    }
+                    if !observed_refs.contains(id) {
+                        observed_refs.push(id.clone());
+                    }
+                }
+
+                let mut file = json!({
+                    "type": "file",
+                    "spec_version": "2.1",
+                    "id": file_id,
+                    "name": name
+                });
+                if let Some(id) = &directory_id {
+                    file["parent_directory_ref"] = json!(id);
+                }
+                if observables.insert(file_id.clone()) {
+                    objects.push(file);
+                }
+                observed_refs.push(file_id);
+
+                let indicator_id = stix_id("indicator", &format!("{}:{}", family, name));
+                let file_first_seen = file_activity.first_seen.map(timestamp).unwrap_or(now.clone());
+                let file_last_seen = file_activity.last_seen.map(timestamp).unwrap_or(now.clone());
+
+                objects.push(json!({
+                    "type": "indicator",
+                    "spec_version": "2.1",
+                    "id": indicator_id,
+                    "created_by_ref": identity_id,
+                    "created": now,
+                    "modified": now,
+                    "name": format!("{} executable {}", family, name),
+                    "indicator_types": ["malicious-activity"],
+                    "pattern": format!("[file:name = '{}']", escape_pattern(name)),
+                    "pattern_type": "stix",
+                    "valid_from": file_first_seen
+                }));
+
+                objects.push(json!({
+                    "type": "relationship",
+                    "spec_version": "2.1",
+                    "id": stix_id("relationship", &format!("{}:indicates:{}", indicator_id, malware_id)),
+                    "created_by_ref": identity_id,
+                    "created": now,
+                    "modified": now,
+                    "relationship_type": "indicates",
+                    "source_ref": indicator_id,
+                    "target_ref": malware_id
+                }));
+
+                let mut sighting = json!({
+                    "type": "sighting",
+                    "spec_version": "2.1",
+                    "id": stix_id("sighting", &format!("{}:{}", intel.archive(), indicator_id)),
+                    "created_by_ref": identity_id,
+                    "created": now,
+                    "modified": now,
+                    "sighting_of_ref": indicator_id,
+                    "count": file_activity.logs,
+                    "first_seen": file_first_seen,
+                    "last_seen": file_last_seen
+                });
+
+                if !file_activity.countries.is_empty() {
+                    sighting["where_sighted_refs"] = json!(file_activity
+                        .countries
+                        .iter()
+                        .map(|country| stix_id("location", country))
+                        .collect::<Vec<_>>());
+                    locations.extend(file_activity.countries.iter().cloned());
+                }
+                objects.push(sighting);
+            }
+
+            if !observed_refs.is_empty() {
+                objects.push(json!({
+                    "type": "observed-data",
+                    "spec_version": "2.1",
+                    "id": stix_id("observed-data", &format!("{}:{}", intel.archive(), family)),
+                    "created_by_ref": identity_id,
+                    "created": now,
+                    "modified": now,
+                    "first_observed": first_seen,
+                    "last_observed": last_seen,
+                    "number_observed": activity.activity.logs.max(1),
+                    "object_refs": observed_refs
+                }));
+            }
+        }
+
+        locations.iter().for_each(|country| {
+            objects.push(json!({
+                "type": "location",
+                "spec_version": "2.1",
+                "id": stix_id("location", country),
+                "created_by_ref": identity_id,
+                "created": now,
+                "modified": now,
+                "country": country
+            }));
+        });
+
+        json!({
+            "type": "bundle",
+            "id": stix_id("bundle", &self.file_name(intel)),
+            "objects": objects
+        })
+    }
+}
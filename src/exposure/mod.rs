@@ -0,0 +1,235 @@
+use std::{
+    io::{Error, ErrorKind, Result},
+    sync::Arc,
+};
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha1::{Digest, Sha1};
+
+use crate::{
+    config::{ExposureAlgorithm, ExposureSettings, PrivacyAction, PrivacySettings},
+    elastic_client::ElasticsearchClient,
+    log_processor::{
+        identity::{Identity, IdentityKind},
+        pass_log_processor::Credential,
+    },
+};
+
+pub mod server;
+
+pub const EXPOSURE_INDEX: &str = "exposures";
+/// Length of the hash prefix sent by clients, as in HIBP range queries.
+pub const PREFIX_LENGTH: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExposureKind {
+    Email,
+    Password,
+}
+
+impl ExposureKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "email" => Some(Self::Email),
+            "password" => Some(Self::Password),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Email => "email",
+            Self::Password => "password",
+        }
+    }
+}
+
+/// A hashed email or password, split at `PREFIX_LENGTH` so range queries
+/// only ever match on the prefix.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExposureRecord {
+    kind: ExposureKind,
+    prefix: String,
+    suffix: String,
+}
+
+pub struct ExposureHasher {
+    key: Option<Vec<u8>>,
+    // password records are only written when the privacy policy keeps or
+    // hashes passwords itself
+    passwords: bool,
+}
+
+impl ExposureHasher {
+    /// `None` when no algorithm is configured and there is no key to default
+    /// to `hmac` with; an explicit `hmac` without a key is an error.
+    pub fn new(settings: &ExposureSettings, privacy: &PrivacySettings) -> Result<Option<Self>> {
+        let key = settings
+            .key
+            .as_ref()
+            .filter(|key| !key.is_empty())
+            .map(|key| key.as_bytes().to_vec());
+
+        let key = match (settings.algorithm, key) {
+            (Some(ExposureAlgorithm::Sha1), _) => None,
+            (_, Some(key)) => Some(key),
+            (None, None) => return Ok(None),
+            (Some(ExposureAlgorithm::Hmac), None) => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "Exposure hashing uses hmac but no key is configured",
+                ))
+            }
+        };
+
+        Ok(Some(Self {
+            key,
+            passwords: matches!(privacy.password, PrivacyAction::Keep | PrivacyAction::Hmac),
+        }))
+    }
+
+    /// Emails are matched on their normalized form; passwords as they are.
+    pub fn normalize(kind: ExposureKind, value: &str) -> Option<String> {
+        match kind {
+            ExposureKind::Email => Identity::classify(value)
+                .filter(|identity| identity.kind() == IdentityKind::Email)
+                .map(|identity| identity.normalized().to_string()),
+            ExposureKind::Password => Some(value.to_string()).filter(|value| !value.is_empty()),
+        }
+    }
+
+    /// Uppercase hex digest, the form HIBP clients expect.
+    pub fn hash(&self, value: &str) -> String {
+        let digest = match &self.key {
+            Some(key) => {
+                let mut mac = Hmac::<Sha1>::new_from_slice(key).expect("hmac accepts any key length");
+                mac.update(value.as_bytes());
+                mac.finalize().into_bytes().to_vec()
+            }
+            None => Sha1::digest(value.as_bytes()).to_vec(),
+        };
+
+        digest.iter().map(|byte| format!("{:02X}", byte)).collect()
+    }
+
+    pub fn record(&self, kind: ExposureKind, value: &str) -> Option<ExposureRecord> {
+        let hash = self.hash(&Self::normalize(kind, value)?);
+        let (prefix, suffix) = hash.split_at(PREFIX_LENGTH);
+
+        Some(ExposureRecord {
+            kind,
+            prefix: prefix.to_string(),
+            suffix: suffix.to_string(),
+        })
+    }
+
+    pub fn records(&self, credential: &Credential) -> Vec<ExposureRecord> {
+        let email = credential
+            .identity()
+            .filter(|identity| identity.kind() == IdentityKind::Email)
+            .and_then(|identity| self.record(ExposureKind::Email, identity.normalized()));
+        let password = credential
+            .password()
+            .filter(|_| self.passwords)
+            .and_then(|password| self.record(ExposureKind::Password, &password));
+
+        email.into_iter().chain(password).collect()
+    }
+}
+
+pub fn parse_prefix(prefix: &str) -> Result<String> {
+    let prefix = prefix.trim().to_uppercase();
+
+    if prefix.len() != PREFIX_LENGTH || !prefix.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("Prefix must be {} hex characters", PREFIX_LENGTH),
+        ));
+    }
+
+    Ok(prefix)
+}
+
+/// k-anonymity lookups against the exposures index: callers only disclose a
+/// hash prefix and get back every known suffix with its sighting count.
+#[derive(Clone)]
+pub struct ExposureLookup {
+    elastic: Arc<ElasticsearchClient>,
+    hasher: Arc<ExposureHasher>,
+}
+
+impl ExposureLookup {
+    pub fn new(elastic: Arc<ElasticsearchClient>, hasher: Arc<ExposureHasher>) -> Self {
+        Self { elastic, hasher }
+    }
+
+    pub async fn range(&self, kind: ExposureKind, prefix: &str) -> Result<Vec<(String, u64)>> {
+        let prefix = parse_prefix(prefix)?;
+
+        let response = self
+            .elastic
+            .search(
+                EXPOSURE_INDEX,
+                json!({
+                    "size": 0,
+                    "query": {
+                        "bool": {
+                            "filter": [
+                                {"term": {"kind": kind.as_str()}},
+                                {"term": {"prefix": prefix}}
+                            ]
+                        }
+                    },
+                    "aggs": {
+                        "suffixes": {"terms": {"field": "suffix", "size": 65536}}
+                    }
+                }),
+            )
+            .await?;
+
+        let buckets = response["aggregations"]["suffixes"]["buckets"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+
+        Ok(buckets
+            .iter()
+            .filter_map(|bucket| Some((bucket["key"].as_str()?.to_string(), bucket["doc_count"].as_u64()?)))
+            .collect())
+    }
+
+    /// Sightings of a plaintext value, hashed locally before querying.
+    pub async fn check(&self, kind: ExposureKind, value: &str) -> Result<u64> {
+        let Some(record) = self.hasher.record(kind, value) else {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Not a valid {}", kind.as_str()),
+            ));
+        };
+
+        Ok(self
+            .range(kind, &record.prefix)
+            .await?
+            .iter()
+            .find(|(suffix, _)| *suffix == record.suffix)
+            .map(|(_, count)| *count)
+            .unwrap_or(0))
+    }
+}
+
+pub fn format_range(entries: &[(String, u64)]) -> String {
+    entries
+        .iter()
+        .map(|(suffix, count)| format!("{}:{}\r\n", suffix, count))
+        .collect()
+}
+
+pub fn to_documents(records: Vec<ExposureRecord>) -> Vec<Value> {
+    records
+        .into_iter()
+        .filter_map(|record| serde_json::to_value(record).ok())
+        .collect()
+}
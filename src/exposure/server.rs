@@ -0,0 +1,76 @@
+use std::io::Result;
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use super::{format_range, ExposureKind, ExposureLookup};
+
+pub const DEFAULT_LISTEN: &str = "127.0.0.1:8787";
+
+fn response(status: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+/// Serves `GET /range/<email|password>/<prefix>` with one `SUFFIX:COUNT`
+/// line per known hash, like the HIBP range API.
+pub struct ExposureServer {
+    lookup: ExposureLookup,
+}
+
+impl ExposureServer {
+    pub fn new(lookup: ExposureLookup) -> Self {
+        Self { lookup }
+    }
+
+    pub async fn listen(&self, address: &str) -> Result<()> {
+        let listener = TcpListener::bind(address).await?;
+        println!("[+] Exposure lookup listening on {}", address);
+
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let lookup = self.lookup.clone();
+
+            tokio::spawn(async move {
+                if let Err(err) = Self::handle(lookup, stream).await {
+                    eprintln!("[-] Exposure lookup error {}", err)
+                }
+            });
+        }
+    }
+
+    async fn handle(lookup: ExposureLookup, mut stream: TcpStream) -> Result<()> {
+        let mut buffer = [0; 4096];
+        let read = stream.read(&mut buffer).await?;
+        let request = String::from_utf8_lossy(&buffer[..read]);
+
+        let mut request_line = request.lines().next().unwrap_or_default().split_whitespace();
+        let (method, path) = (request_line.next(), request_line.next());
+
+        let reply = match (method, path.map(|path| path.trim_matches('/').split('/').collect::<Vec<_>>())) {
+            (Some("GET"), Some(segments)) if segments.len() == 3 && segments[0] == "range" => {
+                match ExposureKind::from_name(segments[1]) {
+                    Some(kind) => match lookup.range(kind, segments[2]).await {
+                        Ok(entries) => response("200 OK", &format_range(&entries)),
+                        Err(err) if err.kind() == std::io::ErrorKind::InvalidInput => {
+                            response("400 Bad Request", &err.to_string())
+                        }
+                        Err(err) => response("502 Bad Gateway", &err.to_string()),
+                    },
+                    None => response("404 Not Found", "Unknown kind"),
+                }
+            }
+            (Some("GET"), _) => response("404 Not Found", "Not Found"),
+            _ => response("405 Method Not Allowed", "Method Not Allowed"),
+        };
+
+        stream.write_all(reply.as_bytes()).await?;
+        stream.shutdown().await
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    identity::{Identity, IdentityKind},
+    info_log_processor::LogInfo,
+    is_separator, LogProcessor,
+};
+
+const FIELD_KEYS: &[&str] = &["name", "field", "key", "form", "input"];
+const VALUE_KEYS: &[&str] = &["value", "data"];
+// Form field names holding a person's name.
+const NAME_FIELDS: &[&str] = &[
+    "name", "fname", "lname", "firstname", "first_name", "first-name", "lastname", "last_name",
+    "last-name", "fullname", "full_name", "surname", "given-name", "family-name", "cc-name",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AutofillKind {
+    Email,
+    Phone,
+    Name,
+    Other,
+}
+
+/// A form field and the value the browser saved for it. `value` goes
+/// through the privacy policy; `email_domain` is kept for attribution.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct AutofillEntry {
+    field: String,
+    kind: AutofillKind,
+    value: String,
+    email_domain: Option<String>,
+}
+
+impl AutofillEntry {
+    fn new(field: &str, value: &str) -> Self {
+        let field = field.trim().to_string();
+        let value = value.trim().to_string();
+        let identity = Identity::classify(&value);
+
+        let (kind, email_domain) = match identity.as_ref().map(|identity| identity.kind()) {
+            Some(IdentityKind::Email) => (AutofillKind::Email, identity.and_then(|identity| identity.email_domain())),
+            Some(IdentityKind::Phone) => (AutofillKind::Phone, None),
+            _ if NAME_FIELDS.contains(&field.to_lowercase().as_str()) => (AutofillKind::Name, None),
+            _ => (AutofillKind::Other, None),
+        };
+
+        Self { field, kind, value, email_domain }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutofillDocument {
+    archive: String,
+    log_folder: String,
+    hwid: Option<String>,
+    country: Option<String>,
+    email_domains: BTreeSet<String>,
+    entries: Vec<AutofillEntry>,
+}
+
+pub struct AutofillLogProcessor {
+    info: LogInfo,
+    archive: String,
+    log_folder: String,
+}
+
+impl LogProcessor for AutofillLogProcessor {
+    type Out = Option<AutofillDocument>;
+
+    /// Reads `Name: ...` / `Value: ...` blocks and `field<TAB>value` rows;
+    /// repeated pairs are kept once.
+    fn parse<C: AsRef<str>>(&self, content: C) -> Self::Out {
+        let mut entries = BTreeSet::new();
+        let mut field: Option<String> = None;
+
+        for line in content.as_ref().lines() {
+            let line = line.trim();
+
+            if line.is_empty() || is_separator(line) {
+                field = None;
+                continue;
+            }
+
+            if let Some((key, value)) = line.split_once(':') {
+                let key = key.trim().to_lowercase();
+
+                if FIELD_KEYS.contains(&key.as_str()) {
+                    field = Some(value.trim().to_string());
+                    continue;
+                }
+                if VALUE_KEYS.contains(&key.as_str()) {
+                    if let Some(field) = field.take().filter(|_| !value.trim().is_empty()) {
+                        entries.insert(AutofillEntry::new(&field, value));
+                    }
+                    continue;
+                }
+            }
+
+            if let Some((name, value)) = line.split_once('\t').filter(|(_, value)| !value.trim().is_empty()) {
+                entries.insert(AutofillEntry::new(name, value));
+            }
+        }
+
+        if entries.is_empty() {
+            return None;
+        }
+
+        Some(AutofillDocument {
+            archive: self.archive.clone(),
+            log_folder: self.log_folder.clone(),
+            hwid: self.info.hwid(),
+            country: self.info.country(),
+            email_domains: entries.iter().filter_map(|entry| entry.email_domain.clone()).collect(),
+            entries: entries.into_iter().collect(),
+        })
+    }
+}
+
+impl AutofillLogProcessor {
+    pub fn new<A: AsRef<str>, L: AsRef<str>>(info: &LogInfo, archive: A, log_folder: L) -> Self {
+        Self {
+            info: info.clone(),
+            archive: archive.as_ref().to_string(),
+            log_folder: log_folder.as_ref().to_string(),
+        }
+    }
+}
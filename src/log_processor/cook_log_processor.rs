@@ -1,113 +1,502 @@
-use std::{collections::HashMap, io::Error};
+use std::{collections::HashMap, io::{Error, ErrorKind, Result}};
 
-use lazy_static::lazy_static;
-use regex::Regex;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-use super::{info_log_processor::LogInfo, LogProcessor};
+use crate::{config::CookieMode, privacy::keyed_hash};
 
-lazy_static! {
-    static ref REGEX_SPLIT_VALUES: Regex = Regex::new(r"[\t]+").unwrap();
+use super::{info_log_processor::{parse_date, GeoInfo, LogInfo}, pass_log_processor::split_application, LogProcessor};
+
+// curl and most stealers mark HttpOnly cookies by prefixing the domain.
+const HTTP_ONLY_PREFIX: &str = "#HttpOnly_";
+const NETSCAPE_COLUMNS: usize = 7;
+// Seconds between the Windows epoch (1601-01-01) Chrome counts from and
+// the Unix epoch.
+const CHROME_EPOCH_OFFSET: f64 = 11_644_473_600.0;
+// 9999-12-31T23:59:59Z, the latest expiry a browser writes in seconds.
+const MAX_EXPIRY_SECONDS: f64 = 253_402_300_799.0;
+
+// Key spellings of the browser extensions and families writing JSON dumps.
+const JSON_DOMAIN: &[&str] = &["domain", "host", "host_key"];
+const JSON_PATH: &[&str] = &["path"];
+const JSON_NAME: &[&str] = &["name"];
+const JSON_VALUE: &[&str] = &["value"];
+const JSON_EXPIRES: &[&str] = &["expirationDate", "expirationdate", "expires", "expiry", "expires_utc"];
+const JSON_SECURE: &[&str] = &["secure", "isSecure", "is_secure"];
+const JSON_HTTP_ONLY: &[&str] = &["httpOnly", "httponly", "isHttpOnly", "is_httponly"];
+const JSON_HOST_ONLY: &[&str] = &["hostOnly", "hostonly"];
+const JSON_SESSION: &[&str] = &["session", "isSession", "is_session"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CookieFormat {
+    Netscape,
+    Json,
+    /// One `name=value; domain=...; path=...` record per line.
+    Semicolon
+}
+
+impl CookieFormat {
+    /// Looks at the first meaningful line: JSON starts with `[` or `{`,
+    /// Netscape lines are tab separated and semicolon dumps are not.
+    pub fn detect(content: &str) -> Self {
+        let trimmed = content.trim_start_matches('\u{feff}').trim_start();
+
+        if trimmed.starts_with('[') || trimmed.starts_with('{') {
+            return Self::Json;
+        }
+
+        let first_line = trimmed
+            .lines()
+            .map(|line| line.strip_prefix(HTTP_ONLY_PREFIX).unwrap_or(line))
+            .find(|line| !line.trim().is_empty() && !line.starts_with('#'));
+
+        match first_line {
+            Some(line) if !line.contains('\t') && line.contains('=') => Self::Semicolon,
+            _ => Self::Netscape
+        }
+    }
 }
 
 pub enum CookieFields {
     Domain(String),
-    HttpOnly(String),
+    IncludeSubdomains(bool),
+    HttpOnly(bool),
     Path(String),
-    Secure(String),
-    ExpiresIn(String),
+    Secure(bool),
+    ExpiresAt(Option<DateTime<Utc>>),
+    ValidAtInfection(Option<bool>),
     Name(String),
     Value(String)
 }
 
+// Folder and file names that say nothing about the browser.
+const GENERIC_NAMES: &[&str] = &["cookies", "cookie", "browsers", "browser", "network"];
+
+/// Where a cookie file was found. Browser and profile come from its path:
+/// `Cookies/Google Chrome_Default.txt`, `Cookies/Chrome [Profile 1].txt`,
+/// `Cookies/Google_[Chrome]_Default.txt` or
+/// `Browsers/Chrome/Default/Cookies.txt`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CookieSource {
+    archive: String,
+    log_folder: String,
+    source_path: String,
+    browser: Option<String>,
+    profile: Option<String>
+}
+
+impl CookieSource {
+    pub fn new<A: AsRef<str>, L: AsRef<str>, P: AsRef<str>>(archive: A, log_folder: L, path: P) -> Self {
+        let (browser, profile) = Self::browser_profile(path.as_ref());
+
+        Self {
+            archive: archive.as_ref().to_string(),
+            log_folder: log_folder.as_ref().to_string(),
+            source_path: path.as_ref().to_string(),
+            browser,
+            profile
+        }
+    }
+
+    fn is_generic(name: &str) -> bool {
+        GENERIC_NAMES.contains(&name.trim().to_lowercase().as_str())
+    }
+
+    fn browser_profile(path: &str) -> (Option<String>, Option<String>) {
+        let mut components: Vec<_> = path.split(['/', '\\']).filter(|part| !part.is_empty()).collect();
+        let Some(filename) = components.pop() else {
+            return (None, None);
+        };
+        let stem = filename.rsplit_once('.').map_or(filename, |(stem, _)| stem);
+
+        if !Self::is_generic(stem) {
+            let (application, profile) = split_application(stem);
+
+            if profile.is_some() {
+                return (Some(application), profile);
+            }
+
+            return match stem.split_once('_') {
+                Some((browser, profile)) => (Some(browser.trim().to_string()), Some(profile.trim().to_string())),
+                None => (Some(application), None)
+            };
+        }
+
+        // The first component is the log folder itself.
+        let folders: Vec<_> = components.iter().skip(1).filter(|part| !Self::is_generic(part)).collect();
+
+        match folders.as_slice() {
+            [.., browser, profile] => (Some(browser.to_string()), Some(profile.to_string())),
+            [browser] => (Some(browser.to_string()), None),
+            [] => (None, None)
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CookieDocument {
     domain: String,
     country: String,
+    geo: Option<GeoInfo>,
+    hwid: Option<String>,
+    #[serde(flatten)]
+    source: CookieSource,
     cookies: Vec<Cookie>
 }
 
 impl CookieDocument {
-    pub fn new(domain: String, country: String, cookies: Vec<Cookie>) -> Self {
-        Self { domain, country, cookies }
+    pub fn new(domain: String, infos: &LogInfo, source: &CookieSource) -> Self {
+        Self {
+            domain,
+            country: infos.country().unwrap_or(String::from("UNK")),
+            geo: infos.geo(),
+            hwid: infos.hwid(),
+            source: source.clone(),
+            cookies: Vec::new()
+        }
+    }
+
+    pub(crate) fn cookies(&self) -> &[Cookie] {
+        &self.cookies
     }
 }
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Cookie {
     domain: String,
-    http_only: String,
+    include_subdomains: bool,
+    http_only: bool,
     path: String,
-    secure: String,
-    expires_in: String,
+    secure: bool,
+    // `None` for session cookies
+    expires_at: Option<DateTime<Utc>>,
+    session: bool,
+    // `None` when the log has no infection date
+    valid_at_infection: Option<bool>,
     name: String,
-    value: String
+    value: Option<String>,
+    value_hash: Option<String>
 }
 
 
 impl Cookie {
     pub fn new() -> Self {
-        Self { domain: String::new(), http_only: String::new(), path: String::new(), secure: String::new(), name: String::new(), value: String::new(), expires_in: String::new() }
+        Self { domain: String::new(), include_subdomains: false, http_only: false, path: String::new(), secure: false, expires_at: None, session: true, valid_at_infection: None, name: String::new(), value: Some(String::new()), value_hash: None }
     }
 
     pub fn set(&mut self, field: CookieFields) {
         
         match field {
             CookieFields::Domain(value) => self.domain.replace_range(.., &value),
-            CookieFields::HttpOnly(value) => self.http_only.replace_range(.., &value),
+            CookieFields::IncludeSubdomains(value) => self.include_subdomains = value,
+            CookieFields::HttpOnly(value) => self.http_only = value,
             CookieFields::Path(value) => self.path.replace_range(.., &value),
-            CookieFields::Secure(value) => self.secure.replace_range(.., &value),
+            CookieFields::Secure(value) => self.secure = value,
             CookieFields::Name(value) => self.name.replace_range(.., &value),
-            CookieFields::Value(value) => self.value.replace_range(.., &value),
-            CookieFields::ExpiresIn(value) => self.expires_in.replace_range(.., &value),
+            CookieFields::Value(value) => self.value = Some(value),
+            CookieFields::ExpiresAt(value) => {
+                self.session = value.is_none();
+                self.expires_at = value
+            },
+            CookieFields::ValidAtInfection(value) => self.valid_at_infection = value,
+        };
+    }
+
+    /// Keeps only what identifies the cookie: the value is replaced by its
+    /// keyed hash, so the same cookie seen in two logs still deduplicates.
+    fn strip_value(&mut self, key: &[u8]) {
+        self.value_hash = self.value.take().map(|value| keyed_hash(key, value));
+    }
+
+    /// Session cookies live as long as the browser did, so they are taken
+    /// as valid when the log was captured.
+    pub fn is_valid_at(&self, date: DateTime<Utc>) -> bool {
+        self.expires_at.is_none_or(|expires_at| expires_at > date)
+    }
+
+    /// Host the cookie belongs to, without the leading `.` of domain cookies.
+    pub fn host(&self) -> &str {
+        self.domain.trim_start_matches('.')
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+    pub(crate) fn valid_at_infection(&self) -> Option<bool> {
+        self.valid_at_infection
+    }
+}
+
+fn is_flag(value: &str) -> bool {
+    value.eq_ignore_ascii_case("TRUE") || value.eq_ignore_ascii_case("FALSE")
+}
+
+fn is_true(value: &str) -> bool {
+    value.trim().eq_ignore_ascii_case("TRUE")
+}
+
+/// Expiry as written by the different browsers and dumps: Unix seconds,
+/// Unix milliseconds, Chrome microseconds since 1601, or an HTTP date.
+/// Numbers are read as seconds unless that lands past the year 9999.
+/// Zero, negative and unreadable values mean a session cookie.
+pub fn parse_expiry(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+
+    if let Ok(number) = value.parse::<f64>() {
+        let seconds = match number {
+            number if number <= 0.0 => return None,
+            // Chrome timestamps are around 1.3e16 and later
+            number if number >= 1e16 => number / 1e6 - CHROME_EPOCH_OFFSET,
+            number if number > MAX_EXPIRY_SECONDS * 1e3 => number / 1e6,
+            number if number > MAX_EXPIRY_SECONDS => number / 1e3,
+            number => number
         };
+
+        return DateTime::from_timestamp(seconds.trunc() as i64, 0);
+    }
+
+    // `Wed, 21 Oct 2026 07:28:00 GMT` or its `21-Oct-2026` variant
+    DateTime::parse_from_rfc2822(value)
+        .or_else(|_| DateTime::parse_from_rfc2822(&value.replace('-', " ")))
+        .map(|date| date.with_timezone(&Utc))
+        .ok()
+        .or_else(|| parse_date(value))
+}
+
+/// Parses the cookie file in whichever format it was written.
+pub fn parse_cookies(content: &str) -> (Vec<Cookie>, Vec<usize>) {
+    match CookieFormat::detect(content) {
+        CookieFormat::Netscape => parse_netscape(content),
+        CookieFormat::Json => parse_json(content),
+        CookieFormat::Semicolon => parse_semicolon(content)
     }
 }
+
+/// Positional reader of the 7-column Netscape format (`domain`,
+/// `include_subdomains`, `path`, `secure`, `expires`, `name`, `value`).
+/// Empty columns are kept, so an empty value or path no longer shifts the
+/// following fields, and the value keeps any tab it contains. Comment lines
+/// are skipped; returns the cookies and the 1-based numbers of malformed
+/// lines.
+pub fn parse_netscape(content: &str) -> (Vec<Cookie>, Vec<usize>) {
+    let mut cookies = Vec::new();
+    let mut malformed = Vec::new();
+
+    content.lines().enumerate().for_each(|(index, line)| {
+        let line = line.trim_end_matches('\r');
+
+        let (line, http_only) = match line.strip_prefix(HTTP_ONLY_PREFIX) {
+            Some(line) => (line, true),
+            None => (line, false)
+        };
+
+        if line.trim().is_empty() || line.starts_with('#') {
+            return;
+        }
+
+        let columns: Vec<_> = line.splitn(NETSCAPE_COLUMNS, '\t').collect();
+
+        let is_valid = columns.len() == NETSCAPE_COLUMNS
+            && !columns[0].trim().is_empty()
+            && is_flag(columns[1].trim())
+            && is_flag(columns[3].trim())
+            && columns[4].trim().parse::<f64>().is_ok();
+
+        if !is_valid {
+            malformed.push(index + 1);
+            return;
+        }
+
+        let mut cookie = Cookie::new();
+        cookie.set(CookieFields::Domain(columns[0].trim().to_string()));
+        cookie.set(CookieFields::IncludeSubdomains(is_true(columns[1])));
+        cookie.set(CookieFields::HttpOnly(http_only));
+        cookie.set(CookieFields::Path(columns[2].to_string()));
+        cookie.set(CookieFields::Secure(is_true(columns[3])));
+        cookie.set(CookieFields::ExpiresAt(parse_expiry(columns[4])));
+        cookie.set(CookieFields::Name(columns[5].to_string()));
+        cookie.set(CookieFields::Value(columns[6].to_string()));
+
+        cookies.push(cookie);
+    });
+
+    (cookies, malformed)
+}
+
+fn json_field<'a>(entry: &'a Value, keys: &[&str]) -> Option<&'a Value> {
+    keys.iter().find_map(|key| entry.get(*key)).filter(|value| !value.is_null())
+}
+
+fn json_string(entry: &Value, keys: &[&str]) -> Option<String> {
+    json_field(entry, keys).map(|value| match value {
+        Value::String(value) => value.clone(),
+        value => value.to_string()
+    })
+}
+
+fn json_bool(entry: &Value, keys: &[&str]) -> Option<bool> {
+    json_field(entry, keys).and_then(|value| match value {
+        Value::Bool(value) => Some(*value),
+        Value::Number(value) => value.as_i64().map(|value| value != 0),
+        Value::String(value) => Some(value.eq_ignore_ascii_case("true") || value == "1"),
+        _ => None
+    })
+}
+
+/// Reads a JSON array of cookie objects, or an object holding it under
+/// `cookies`. Malformed entries are reported by their 1-based position; a
+/// syntax error by its line.
+pub fn parse_json(content: &str) -> (Vec<Cookie>, Vec<usize>) {
+    let value = match serde_json::from_str::<Value>(content.trim_start_matches('\u{feff}')) {
+        Ok(value) => value,
+        Err(err) => return (Vec::new(), vec![err.line()])
+    };
+
+    let entries = match &value {
+        Value::Array(entries) => entries.clone(),
+        Value::Object(object) => object.get("cookies").and_then(Value::as_array).cloned().unwrap_or_default(),
+        _ => Vec::new()
+    };
+
+    let mut cookies = Vec::new();
+    let mut malformed = Vec::new();
+
+    entries.iter().enumerate().for_each(|(index, entry)| {
+        let (Some(domain), Some(name)) = (json_string(entry, JSON_DOMAIN), json_string(entry, JSON_NAME)) else {
+            malformed.push(index + 1);
+            return;
+        };
+
+        let include_subdomains = match json_bool(entry, JSON_HOST_ONLY) {
+            Some(host_only) => !host_only,
+            None => domain.starts_with('.')
+        };
+
+        let expires_at = match json_bool(entry, JSON_SESSION) {
+            Some(true) => None,
+            _ => json_string(entry, JSON_EXPIRES).and_then(|expires| parse_expiry(&expires))
+        };
+
+        let mut cookie = Cookie::new();
+        cookie.set(CookieFields::Domain(domain));
+        cookie.set(CookieFields::IncludeSubdomains(include_subdomains));
+        cookie.set(CookieFields::HttpOnly(json_bool(entry, JSON_HTTP_ONLY).unwrap_or(false)));
+        cookie.set(CookieFields::Path(json_string(entry, JSON_PATH).unwrap_or_default()));
+        cookie.set(CookieFields::Secure(json_bool(entry, JSON_SECURE).unwrap_or(false)));
+        cookie.set(CookieFields::ExpiresAt(expires_at));
+        cookie.set(CookieFields::Name(name));
+        cookie.set(CookieFields::Value(json_string(entry, JSON_VALUE).unwrap_or_default()));
+
+        cookies.push(cookie);
+    });
+
+    (cookies, malformed)
+}
+
+/// Reads `name=value; domain=.example.com; path=/; expires=...; secure;
+/// httponly` records, one per line. Lines without a domain are malformed.
+pub fn parse_semicolon(content: &str) -> (Vec<Cookie>, Vec<usize>) {
+    let mut cookies = Vec::new();
+    let mut malformed = Vec::new();
+
+    content.lines().enumerate().for_each(|(index, line)| {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            return;
+        }
+
+        let mut parts = line.split(';').map(|part| part.trim());
+        let Some((name, value)) = parts.next().and_then(|part| part.split_once('=')) else {
+            malformed.push(index + 1);
+            return;
+        };
+
+        let mut cookie = Cookie::new();
+        cookie.set(CookieFields::Name(name.trim().to_string()));
+        cookie.set(CookieFields::Value(value.trim().to_string()));
+
+        parts.for_each(|part| {
+            let (key, value) = part.split_once('=').unwrap_or((part, ""));
+            let value = value.trim().to_string();
+
+            match key.trim().to_lowercase().as_str() {
+                "domain" | "host" => cookie.set(CookieFields::Domain(value)),
+                "path" => cookie.set(CookieFields::Path(value)),
+                "expires" | "expiry" | "expirationdate" => cookie.set(CookieFields::ExpiresAt(parse_expiry(&value))),
+                "secure" => cookie.set(CookieFields::Secure(value.is_empty() || is_true(&value))),
+                "httponly" => cookie.set(CookieFields::HttpOnly(value.is_empty() || is_true(&value))),
+                _ => {}
+            }
+        });
+
+        if cookie.domain.is_empty() {
+            malformed.push(index + 1);
+            return;
+        }
+
+        cookie.set(CookieFields::IncludeSubdomains(cookie.domain.starts_with('.')));
+        cookies.push(cookie);
+    });
+
+    (cookies, malformed)
+}
+
+/// Configuration shared by every `CookieLogProcessor` of a run.
+#[derive(Clone, Debug, Default)]
+pub struct CookieOptions {
+    // set in metadata mode
+    value_key: Option<Vec<u8>>
+}
+
+impl CookieOptions {
+    pub fn new(mode: CookieMode, key: Option<&str>) -> Result<Self> {
+        let value_key = match mode {
+            CookieMode::Full => None,
+            CookieMode::Metadata => Some(
+                key.filter(|key| !key.is_empty())
+                    .map(|key| key.as_bytes().to_vec())
+                    .ok_or(Error::new(ErrorKind::InvalidInput, "Cookie metadata mode needs a privacy key"))?
+            )
+        };
+
+        Ok(Self { value_key })
+    }
+
+    pub fn is_metadata_only(&self) -> bool {
+        self.value_key.is_some()
+    }
+}
+
 pub struct CookieLogProcessor {
-    infos: LogInfo
+    infos: LogInfo,
+    source: CookieSource,
+    options: CookieOptions
 }
 
 impl LogProcessor for CookieLogProcessor {
-    type Out = Result<HashMap<String, CookieDocument>, std::io::Error>;
+    type Out = Result<HashMap<String, CookieDocument>>;
 
     fn parse<C: AsRef<str>>(&self, content: C) -> Self::Out {
         let mut cookies_map = HashMap::new();
+        let (cookies, malformed) = parse_cookies(content.as_ref());
 
-        content.as_ref()
-        .lines().for_each(|line| {
-            let mut cookie = Cookie::new();
-            let log_info = self.infos.clone();
-
-            let explode: Vec<_> = REGEX_SPLIT_VALUES.split(line)
-            .map(|item| item.trim())
-            .map(|item| {
-                if item.starts_with(".") {
-                    item.replacen(".", "", 1)
-                } else {
-                    item.to_string()
-                }
-            }).filter_map(|item| {
-                if item.is_empty() {
-                    None
-                } else {
-                    Some(item)
-                }
-            })
-            .collect();
-            
-   
-            if explode.len() == 7 {
-                cookie.set(CookieFields::Domain(explode[0].to_string()));
-                cookie.set(CookieFields::HttpOnly(explode[1].to_string()));
-                cookie.set(CookieFields::Path(explode[2].to_string()));
-                cookie.set(CookieFields::Secure(explode[3].to_string()));
-                cookie.set(CookieFields::ExpiresIn(explode[4].to_string()));
-                cookie.set(CookieFields::Name(explode[5].to_string()));
-                cookie.set(CookieFields::Value(explode[6].to_string()));
-
-                let entry = cookies_map.entry(cookie.domain.clone())
-                .or_insert(CookieDocument::new(cookie.domain.clone(), log_info.country().unwrap_or(String::from("UNK")), Vec::new()));
-                entry.cookies.push(cookie)
+        if !malformed.is_empty() {
+            eprintln!("[-] Cookie malformed lines in {} {:?}", self.source.source_path, malformed);
+        }
+
+        let infection_date = self.infos.infection_date();
+
+        cookies.into_iter().for_each(|mut cookie| {
+            cookie.set(CookieFields::ValidAtInfection(infection_date.map(|date| cookie.is_valid_at(date))));
+            if let Some(key) = &self.options.value_key {
+                cookie.strip_value(key);
             }
+            let domain = cookie.host().to_string();
+
+            let entry = cookies_map.entry(domain.clone())
+            .or_insert(CookieDocument::new(domain, &self.infos, &self.source));
+            entry.cookies.push(cookie)
         });
 
         if cookies_map.is_empty() {
@@ -119,7 +508,7 @@ impl LogProcessor for CookieLogProcessor {
 }
 
 impl CookieLogProcessor {
-    pub fn new(infos: &LogInfo) -> Self {
-        Self {infos: infos.clone()}
+    pub fn new(infos: &LogInfo, source: CookieSource, options: &CookieOptions) -> Self {
+        Self {infos: infos.clone(), source, options: options.clone()}
     }
 }
\ No newline at end of file
@@ -0,0 +1,398 @@
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+
+/// ISO-3166 alpha-2, alpha-3 and English short name of every assigned code.
+static COUNTRIES: &[(&str, &str, &str)] = &[
+    ("AF", "AFG", "Afghanistan"),
+    ("AX", "ALA", "Aland Islands"),
+    ("AL", "ALB", "Albania"),
+    ("DZ", "DZA", "Algeria"),
+    ("AS", "ASM", "American Samoa"),
+    ("AD", "AND", "Andorra"),
+    ("AO", "AGO", "Angola"),
+    ("AI", "AIA", "Anguilla"),
+    ("AQ", "ATA", "Antarctica"),
+    ("AG", "ATG", "Antigua and Barbuda"),
+    ("AR", "ARG", "Argentina"),
+    ("AM", "ARM", "Armenia"),
+    ("AW", "ABW", "Aruba"),
+    ("AU", "AUS", "Australia"),
+    ("AT", "AUT", "Austria"),
+    ("AZ", "AZE", "Azerbaijan"),
+    ("BS", "BHS", "Bahamas"),
+    ("BH", "BHR", "Bahrain"),
+    ("BD", "BGD", "Bangladesh"),
+    ("BB", "BRB", "Barbados"),
+    ("BY", "BLR", "Belarus"),
+    ("BE", "BEL", "Belgium"),
+    ("BZ", "BLZ", "Belize"),
+    ("BJ", "BEN", "Benin"),
+    ("BM", "BMU", "Bermuda"),
+    ("BT", "BTN", "Bhutan"),
+    ("BO", "BOL", "Bolivia"),
+    ("BQ", "BES", "Bonaire, Sint Eustatius and Saba"),
+    ("BA", "BIH", "Bosnia and Herzegovina"),
+    ("BW", "BWA", "Botswana"),
+    ("BV", "BVT", "Bouvet Island"),
+    ("BR", "BRA", "Brazil"),
+    ("IO", "IOT", "British Indian Ocean Territory"),
+    ("BN", "BRN", "Brunei Darussalam"),
+    ("BG", "BGR", "Bulgaria"),
+    ("BF", "BFA", "Burkina Faso"),
+    ("BI", "BDI", "Burundi"),
+    ("CV", "CPV", "Cabo Verde"),
+    ("KH", "KHM", "Cambodia"),
+    ("CM", "CMR", "Cameroon"),
+    ("CA", "CAN", "Canada"),
+    ("KY", "CYM", "Cayman Islands"),
+    ("CF", "CAF", "Central African Republic"),
+    ("TD", "TCD", "Chad"),
+    ("CL", "CHL", "Chile"),
+    ("CN", "CHN", "China"),
+    ("CX", "CXR", "Christmas Island"),
+    ("CC", "CCK", "Cocos (Keeling) Islands"),
+    ("CO", "COL", "Colombia"),
+    ("KM", "COM", "Comoros"),
+    ("CG", "COG", "Congo"),
+    ("CD", "COD", "Congo, Democratic Republic of the"),
+    ("CK", "COK", "Cook Islands"),
+    ("CR", "CRI", "Costa Rica"),
+    ("CI", "CIV", "Cote d'Ivoire"),
+    ("HR", "HRV", "Croatia"),
+    ("CU", "CUB", "Cuba"),
+    ("CW", "CUW", "Curacao"),
+    ("CY", "CYP", "Cyprus"),
+    ("CZ", "CZE", "Czechia"),
+    ("DK", "DNK", "Denmark"),
+    ("DJ", "DJI", "Djibouti"),
+    ("DM", "DMA", "Dominica"),
+    ("DO", "DOM", "Dominican Republic"),
+    ("EC", "ECU", "Ecuador"),
+    ("EG", "EGY", "Egypt"),
+    ("SV", "SLV", "El Salvador"),
+    ("GQ", "GNQ", "Equatorial Guinea"),
+    ("ER", "ERI", "Eritrea"),
+    ("EE", "EST", "Estonia"),
+    ("SZ", "SWZ", "Eswatini"),
+    ("ET", "ETH", "Ethiopia"),
+    ("FK", "FLK", "Falkland Islands"),
+    ("FO", "FRO", "Faroe Islands"),
+    ("FJ", "FJI", "Fiji"),
+    ("FI", "FIN", "Finland"),
+    ("FR", "FRA", "France"),
+    ("GF", "GUF", "French Guiana"),
+    ("PF", "PYF", "French Polynesia"),
+    ("TF", "ATF", "French Southern Territories"),
+    ("GA", "GAB", "Gabon"),
+    ("GM", "GMB", "Gambia"),
+    ("GE", "GEO", "Georgia"),
+    ("DE", "DEU", "Germany"),
+    ("GH", "GHA", "Ghana"),
+    ("GI", "GIB", "Gibraltar"),
+    ("GR", "GRC", "Greece"),
+    ("GL", "GRL", "Greenland"),
+    ("GD", "GRD", "Grenada"),
+    ("GP", "GLP", "Guadeloupe"),
+    ("GU", "GUM", "Guam"),
+    ("GT", "GTM", "Guatemala"),
+    ("GG", "GGY", "Guernsey"),
+    ("GN", "GIN", "Guinea"),
+    ("GW", "GNB", "Guinea-Bissau"),
+    ("GY", "GUY", "Guyana"),
+    ("HT", "HTI", "Haiti"),
+    ("HM", "HMD", "Heard Island and McDonald Islands"),
+    ("VA", "VAT", "Holy See"),
+    ("HN", "HND", "Honduras"),
+    ("HK", "HKG", "Hong Kong"),
+    ("HU", "HUN", "Hungary"),
+    ("IS", "ISL", "Iceland"),
+    ("IN", "IND", "India"),
+    ("ID", "IDN", "Indonesia"),
+    ("IR", "IRN", "Iran"),
+    ("IQ", "IRQ", "Iraq"),
+    ("IE", "IRL", "Ireland"),
+    ("IM", "IMN", "Isle of Man"),
+    ("IL", "ISR", "Israel"),
+    ("IT", "ITA", "Italy"),
+    ("JM", "JAM", "Jamaica"),
+    ("JP", "JPN", "Japan"),
+    ("JE", "JEY", "Jersey"),
+    ("JO", "JOR", "Jordan"),
+    ("KZ", "KAZ", "Kazakhstan"),
+    ("KE", "KEN", "Kenya"),
+    ("KI", "KIR", "Kiribati"),
+    ("KP", "PRK", "North Korea"),
+    ("KR", "KOR", "South Korea"),
+    ("KW", "KWT", "Kuwait"),
+    ("KG", "KGZ", "Kyrgyzstan"),
+    ("LA", "LAO", "Laos"),
+    ("LV", "LVA", "Latvia"),
+    ("LB", "LBN", "Lebanon"),
+    ("LS", "LSO", "Lesotho"),
+    ("LR", "LBR", "Liberia"),
+    ("LY", "LBY", "Libya"),
+    ("LI", "LIE", "Liechtenstein"),
+    ("LT", "LTU", "Lithuania"),
+    ("LU", "LUX", "Luxembourg"),
+    ("MO", "MAC", "Macao"),
+    ("MG", "MDG", "Madagascar"),
+    ("MW", "MWI", "Malawi"),
+    ("MY", "MYS", "Malaysia"),
+    ("MV", "MDV", "Maldives"),
+    ("ML", "MLI", "Mali"),
+    ("MT", "MLT", "Malta"),
+    ("MH", "MHL", "Marshall Islands"),
+    ("MQ", "MTQ", "Martinique"),
+    ("MR", "MRT", "Mauritania"),
+    ("MU", "MUS", "Mauritius"),
+    ("YT", "MYT", "Mayotte"),
+    ("MX", "MEX", "Mexico"),
+    ("FM", "FSM", "Micronesia"),
+    ("MD", "MDA", "Moldova"),
+    ("MC", "MCO", "Monaco"),
+    ("MN", "MNG", "Mongolia"),
+    ("ME", "MNE", "Montenegro"),
+    ("MS", "MSR", "Montserrat"),
+    ("MA", "MAR", "Morocco"),
+    ("MZ", "MOZ", "Mozambique"),
+    ("MM", "MMR", "Myanmar"),
+    ("NA", "NAM", "Namibia"),
+    ("NR", "NRU", "Nauru"),
+    ("NP", "NPL", "Nepal"),
+    ("NL", "NLD", "Netherlands"),
+    ("NC", "NCL", "New Caledonia"),
+    ("NZ", "NZL", "New Zealand"),
+    ("NI", "NIC", "Nicaragua"),
+    ("NE", "NER", "Niger"),
+    ("NG", "NGA", "Nigeria"),
+    ("NU", "NIU", "Niue"),
+    ("NF", "NFK", "Norfolk Island"),
+    ("MK", "MKD", "North Macedonia"),
+    ("MP", "MNP", "Northern Mariana Islands"),
+    ("NO", "NOR", "Norway"),
+    ("OM", "OMN", "Oman"),
+    ("PK", "PAK", "Pakistan"),
+    ("PW", "PLW", "Palau"),
+    ("PS", "PSE", "Palestine"),
+    ("PA", "PAN", "Panama"),
+    ("PG", "PNG", "Papua New Guinea"),
+    ("PY", "PRY", "Paraguay"),
+    ("PE", "PER", "Peru"),
+    ("PH", "PHL", "Philippines"),
+    ("PN", "PCN", "Pitcairn"),
+    ("PL", "POL", "Poland"),
+    ("PT", "PRT", "Portugal"),
+    ("PR", "PRI", "Puerto Rico"),
+    ("QA", "QAT", "Qatar"),
+    ("RE", "REU", "Reunion"),
+    ("RO", "ROU", "Romania"),
+    ("RU", "RUS", "Russia"),
+    ("RW", "RWA", "Rwanda"),
+    ("BL", "BLM", "Saint Barthelemy"),
+    ("SH", "SHN", "Saint Helena"),
+    ("KN", "KNA", "Saint Kitts and Nevis"),
+    ("LC", "LCA", "Saint Lucia"),
+    ("MF", "MAF", "Saint Martin"),
+    ("PM", "SPM", "Saint Pierre and Miquelon"),
+    ("VC", "VCT", "Saint Vincent and the Grenadines"),
+    ("WS", "WSM", "Samoa"),
+    ("SM", "SMR", "San Marino"),
+    ("ST", "STP", "Sao Tome and Principe"),
+    ("SA", "SAU", "Saudi Arabia"),
+    ("SN", "SEN", "Senegal"),
+    ("RS", "SRB", "Serbia"),
+    ("SC", "SYC", "Seychelles"),
+    ("SL", "SLE", "Sierra Leone"),
+    ("SG", "SGP", "Singapore"),
+    ("SX", "SXM", "Sint Maarten"),
+    ("SK", "SVK", "Slovakia"),
+    ("SI", "SVN", "Slovenia"),
+    ("SB", "SLB", "Solomon Islands"),
+    ("SO", "SOM", "Somalia"),
+    ("ZA", "ZAF", "South Africa"),
+    ("GS", "SGS", "South Georgia and the South Sandwich Islands"),
+    ("SS", "SSD", "South Sudan"),
+    ("ES", "ESP", "Spain"),
+    ("LK", "LKA", "Sri Lanka"),
+    ("SD", "SDN", "Sudan"),
+    ("SR", "SUR", "Suriname"),
+    ("SJ", "SJM", "Svalbard and Jan Mayen"),
+    ("SE", "SWE", "Sweden"),
+    ("CH", "CHE", "Switzerland"),
+    ("SY", "SYR", "Syria"),
+    ("TW", "TWN", "Taiwan"),
+    ("TJ", "TJK", "Tajikistan"),
+    ("TZ", "TZA", "Tanzania"),
+    ("TH", "THA", "Thailand"),
+    ("TL", "TLS", "Timor-Leste"),
+    ("TG", "TGO", "Togo"),
+    ("TK", "TKL", "Tokelau"),
+    ("TO", "TON", "Tonga"),
+    ("TT", "TTO", "Trinidad and Tobago"),
+    ("TN", "TUN", "Tunisia"),
+    ("TR", "TUR", "Turkey"),
+    ("TM", "TKM", "Turkmenistan"),
+    ("TC", "TCA", "Turks and Caicos Islands"),
+    ("TV", "TUV", "Tuvalu"),
+    ("UG", "UGA", "Uganda"),
+    ("UA", "UKR", "Ukraine"),
+    ("AE", "ARE", "United Arab Emirates"),
+    ("GB", "GBR", "United Kingdom"),
+    ("US", "USA", "United States"),
+    ("UM", "UMI", "United States Minor Outlying Islands"),
+    ("UY", "URY", "Uruguay"),
+    ("UZ", "UZB", "Uzbekistan"),
+    ("VU", "VUT", "Vanuatu"),
+    ("VE", "VEN", "Venezuela"),
+    ("VN", "VNM", "Vietnam"),
+    ("VG", "VGB", "British Virgin Islands"),
+    ("VI", "VIR", "U.S. Virgin Islands"),
+    ("WF", "WLF", "Wallis and Futuna"),
+    ("EH", "ESH", "Western Sahara"),
+    ("YE", "YEM", "Yemen"),
+    ("ZM", "ZMB", "Zambia"),
+    ("ZW", "ZWE", "Zimbabwe"),
+];
+
+/// Alternative and localized spellings observed in info files, keyed by the
+/// alpha-2 code they resolve to.
+static ALIASES: &[(&str, &[&str])] = &[
+    ("US", &["united states of america", "america", "estados unidos", "états-unis", "etats-unis", "vereinigte staaten", "stati uniti", "соединенные штаты", "сша"]),
+    ("GB", &["uk", "great britain", "britain", "england", "scotland", "wales", "northern ireland", "united kingdom of great britain and northern ireland", "reino unido", "royaume-uni", "vereinigtes königreich", "regno unito", "великобритания"]),
+    ("DE", &["deutschland", "alemania", "allemagne", "germania", "alemanha", "niemcy", "германия"]),
+    ("FR", &["frankreich", "francia", "frança", "francja", "франция"]),
+    ("ES", &["españa", "espana", "spanien", "espagne", "espanha", "hiszpania", "испания"]),
+    ("IT", &["italia", "italien", "italie", "włochy", "италия"]),
+    ("PT", &["portugalia", "португалия"]),
+    ("BR", &["brasil", "brésil", "bresil", "brasilien", "бразилия"]),
+    ("MX", &["méxico", "mexique", "mexiko", "мексика"]),
+    ("AR", &["аргентина"]),
+    ("PE", &["perú"]),
+    ("RU", &["russian federation", "rusia", "russland", "russie", "rosja", "россия", "российская федерация"]),
+    ("UA", &["ucrania", "ukraina", "україна", "украина"]),
+    ("BY", &["беларусь", "белоруссия", "білорусь"]),
+    ("KZ", &["казахстан", "qazaqstan"]),
+    ("UZ", &["o'zbekiston", "узбекистан"]),
+    ("KG", &["kyrgyz republic", "кыргызстан", "киргизия"]),
+    ("TJ", &["таджикистан"]),
+    ("AZ", &["azərbaycan", "азербайджан"]),
+    ("AM", &["հայաստան", "армения"]),
+    ("GE", &["საქართველო", "грузия"]),
+    ("MD", &["moldova, republic of", "republic of moldova", "молдова"]),
+    ("PL", &["polska", "polen", "polonia", "pologne", "польша"]),
+    ("CZ", &["czech republic", "česko", "česká republika", "чехия"]),
+    ("SK", &["slovensko", "словакия"]),
+    ("SI", &["slovenija"]),
+    ("HU", &["magyarország", "венгрия"]),
+    ("RO", &["românia", "румыния"]),
+    ("BG", &["българия", "болгария"]),
+    ("RS", &["srbija", "србија", "сербия"]),
+    ("HR", &["hrvatska"]),
+    ("GR", &["hellas", "grecia", "griechenland", "ελλάδα", "греция"]),
+    ("NL", &["the netherlands", "nederland", "holland", "niederlande", "países bajos", "pays-bas", "нидерланды"]),
+    ("BE", &["belgië", "belgique", "belgien"]),
+    ("CH", &["schweiz", "suisse", "svizzera", "suiza"]),
+    ("AT", &["österreich", "osterreich"]),
+    ("SE", &["sverige", "suecia"]),
+    ("NO", &["norge", "noruega"]),
+    ("DK", &["danmark", "dinamarca"]),
+    ("FI", &["suomi"]),
+    ("EE", &["eesti"]),
+    ("LV", &["latvija"]),
+    ("LT", &["lietuva"]),
+    ("IE", &["éire", "eire"]),
+    ("TR", &["türkiye", "turkiye", "turquía", "turquia", "türkei", "турция"]),
+    ("IL", &["ישראל"]),
+    ("SA", &["kingdom of saudi arabia", "arabia saudita", "السعودية"]),
+    ("AE", &["uae", "emirates", "الإمارات"]),
+    ("EG", &["egipto", "مصر"]),
+    ("DZ", &["algérie"]),
+    ("MA", &["maroc"]),
+    ("TN", &["tunisie"]),
+    ("IN", &["bharat", "भारत", "индия"]),
+    ("PK", &["پاکستان"]),
+    ("CN", &["people's republic of china", "prc", "中国"]),
+    ("TW", &["taiwan, province of china", "republic of china", "台灣", "台湾"]),
+    ("HK", &["hong kong sar", "hong kong, china", "香港"]),
+    ("JP", &["japón", "japon", "日本"]),
+    ("KR", &["korea", "republic of korea", "korea, republic of", "대한민국", "한국"]),
+    ("KP", &["democratic people's republic of korea", "korea, democratic people's republic of"]),
+    ("VN", &["viet nam", "việt nam"]),
+    ("TH", &["ประเทศไทย"]),
+    ("ID", &["indonésie", "indonesien"]),
+    ("PH", &["pilipinas"]),
+    ("IR", &["iran, islamic republic of", "islamic republic of iran"]),
+    ("SY", &["syrian arab republic"]),
+    ("LA", &["lao people's democratic republic", "lao pdr"]),
+    ("BO", &["bolivia, plurinational state of"]),
+    ("VE", &["venezuela, bolivarian republic of"]),
+    ("TZ", &["tanzania, united republic of", "united republic of tanzania"]),
+    ("CI", &["ivory coast", "côte d'ivoire"]),
+    ("MK", &["macedonia", "republic of north macedonia"]),
+    ("SZ", &["swaziland"]),
+    ("CV", &["cape verde"]),
+    ("TL", &["east timor"]),
+    ("MM", &["burma"]),
+    ("CD", &["dr congo", "drc", "democratic republic of the congo", "congo (kinshasa)"]),
+    ("CG", &["republic of the congo", "congo (brazzaville)"]),
+    ("PS", &["palestine, state of", "palestinian territory"]),
+    ("VA", &["vatican", "vatican city"]),
+    ("BN", &["brunei"]),
+    ("RE", &["réunion"]),
+    ("CW", &["curaçao"]),
+    ("AX", &["åland islands"]),
+    ("BL", &["saint barthélemy"]),
+    ("FM", &["micronesia, federated states of"]),
+    ("VG", &["virgin islands, british"]),
+    ("VI", &["virgin islands, u.s.", "us virgin islands"]),
+    ("MO", &["macau"]),
+];
+
+lazy_static! {
+    static ref COUNTRY_TABLE: HashMap<String, &'static str> = {
+        let mut table = HashMap::new();
+
+        COUNTRIES.iter().for_each(|(alpha2, alpha3, name)| {
+            table.insert(normalize_key(alpha2), *alpha2);
+            table.insert(normalize_key(alpha3), *alpha2);
+            table.insert(normalize_key(name), *alpha2);
+        });
+
+        ALIASES.iter().for_each(|(alpha2, spellings)| {
+            spellings.iter().for_each(|spelling| {
+                table.insert(normalize_key(spelling), *alpha2);
+            });
+        });
+
+        table
+    };
+}
+
+fn normalize_key(value: &str) -> String {
+    value
+        .to_lowercase()
+        .replace('.', "")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Resolves a self-reported country string to its ISO-3166 alpha-2 code.
+///
+/// The whole value is tried first so names containing commas or parentheses
+/// still match, then the part before any `(` or `,`, which covers values such
+/// as `US (United States)` or `Brazil, Sao Paulo`.
+pub fn to_alpha2(value: &str) -> Option<&'static str> {
+    let candidates = [
+        Some(value),
+        value.split(['(', ',']).next(),
+    ];
+
+    candidates
+        .into_iter()
+        .flatten()
+        .map(normalize_key)
+        .filter(|key| !key.is_empty())
+        .find_map(|key| COUNTRY_TABLE.get(&key).copied())
+}
@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+use url::{Host, Url};
+
+use super::public_suffix;
+
+type UrlPart = Option<String>;
+
+/// Components of a credential url, indexed next to the raw value so exposure
+/// can be searched by registrable domain instead of wildcards over urls.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UrlParts {
+    scheme: UrlPart,
+    host: UrlPart,
+    port: Option<u16>,
+    path: UrlPart,
+    domain: UrlPart,
+    subdomain: UrlPart,
+    package: UrlPart
+}
+
+impl UrlParts {
+    /// Parses full urls, bare hosts (`example.com/login`, `10.0.0.1:8080`)
+    /// and `android://<hash>@<package>/` app urls, whose package name is
+    /// kept instead of a host.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+
+        if value.is_empty() {
+            return None;
+        }
+
+        let has_scheme = value.contains("://");
+        let url = if has_scheme {
+            Url::parse(value).ok()?
+        } else {
+            Url::parse(&format!("http://{}", value)).ok()?
+        };
+
+        let mut parts = Self {
+            scheme: Some(url.scheme().to_string()).filter(|_| has_scheme),
+            port: url.port(),
+            path: Some(url.path().to_string()).filter(|path| !path.is_empty() && path != "/"),
+            ..Self::default()
+        };
+
+        if url.scheme() == "android" {
+            parts.package = url.host_str().map(|package| package.to_lowercase());
+            return Some(parts);
+        }
+
+        match url.host()? {
+            Host::Domain(domain) => {
+                let host = domain.to_lowercase();
+                let (registrable, subdomain) = public_suffix::split_host(&host);
+
+                parts.host = Some(host);
+                parts.domain = registrable;
+                parts.subdomain = subdomain;
+            },
+            Host::Ipv4(address) => parts.host = Some(address.to_string()),
+            Host::Ipv6(address) => parts.host = Some(address.to_string())
+        }
+
+        Some(parts)
+    }
+
+    pub(crate) fn host(&self) -> UrlPart {
+        self.host.clone()
+    }
+    pub(crate) fn domain(&self) -> UrlPart {
+        self.domain.clone()
+    }
+    pub(crate) fn package(&self) -> UrlPart {
+        self.package.clone()
+    }
+}
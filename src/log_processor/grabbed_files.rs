@@ -0,0 +1,103 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::archive::{ArchiveFilter, FileDigest};
+
+use super::{info_log_processor::LogInfo, log_filter::LogFilter};
+
+lazy_static! {
+    // Folders stealers copy desktop and document files into.
+    static ref REGEX_GRABBER_FOLDER: Regex = Regex::new(r"(?i)/(?:file ?grabber|grabber|grabbed ?files|files|desktop|documents)/").unwrap();
+}
+
+const DOCUMENT_EXTENSIONS: &[&str] = &[
+    "doc", "docx", "odt", "rtf", "pdf", "txt", "xls", "xlsx", "ods", "csv", "ppt", "pptx", "odp",
+];
+const KEY_EXTENSIONS: &[&str] = &[
+    "key", "pem", "ppk", "pfx", "p12", "asc", "gpg", "kdbx", "keystore", "wallet",
+];
+const KEY_NAMES: &[&str] = &["id_rsa", "id_dsa", "id_ecdsa", "id_ed25519", "wallet.dat"];
+const CONFIG_EXTENSIONS: &[&str] = &[
+    "json", "xml", "ini", "conf", "cfg", "config", "yaml", "yml", "toml", "env", "ovpn", "rdp",
+];
+
+/// True for files stolen by the grabber; their content is never parsed.
+pub fn is_grabbed(path: &str) -> bool {
+    REGEX_GRABBER_FOLDER.is_match(path)
+}
+
+/// Filter selecting every grabbed file of the archive, whatever its
+/// extension.
+pub fn grabber_filter() -> LogFilter {
+    LogFilter::new(Some(vec![REGEX_GRABBER_FOLDER.clone()]), None::<Vec<String>>)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GrabbedCategory {
+    Documents,
+    Keys,
+    Configs,
+    Other,
+}
+
+impl GrabbedCategory {
+    fn classify(file_name: &str, extension: Option<&str>) -> Self {
+        let file_name = file_name.to_lowercase();
+
+        if KEY_NAMES.contains(&file_name.as_str()) {
+            return Self::Keys;
+        }
+
+        match extension {
+            Some(extension) if KEY_EXTENSIONS.contains(&extension) => Self::Keys,
+            Some(extension) if DOCUMENT_EXTENSIONS.contains(&extension) => Self::Documents,
+            Some(extension) if CONFIG_EXTENSIONS.contains(&extension) => Self::Configs,
+            _ => Self::Other,
+        }
+    }
+}
+
+/// Inventory entry of a grabbed file: where it was and what it hashes to,
+/// never what it contains.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrabbedFileDocument {
+    archive: String,
+    log_folder: String,
+    hwid: Option<String>,
+    path: String,
+    file_name: String,
+    extension: Option<String>,
+    size: u64,
+    sha256: String,
+    category: GrabbedCategory,
+}
+
+impl GrabbedFileDocument {
+    pub fn new<A: AsRef<str>, L: AsRef<str>>(archive: A, log_folder: L, path: &str, info: &LogInfo, digest: FileDigest) -> Self {
+        let log_folder = log_folder.as_ref();
+        let file_name = path.rsplit('/').next().unwrap_or(path).to_string();
+        let extension = file_name
+            .rsplit_once('.')
+            .map(|(_, extension)| extension.to_lowercase())
+            .filter(|extension| !extension.is_empty());
+        let category = GrabbedCategory::classify(&file_name, extension.as_deref());
+
+        Self {
+            archive: archive.as_ref().to_string(),
+            log_folder: log_folder.to_string(),
+            hwid: info.hwid(),
+            path: path
+                .strip_prefix(log_folder)
+                .unwrap_or(path)
+                .trim_start_matches('/')
+                .to_string(),
+            file_name,
+            extension,
+            size: digest.size,
+            sha256: digest.sha256,
+            category,
+        }
+    }
+}
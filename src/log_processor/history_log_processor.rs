@@ -0,0 +1,156 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    cook_log_processor::parse_expiry, credential_url::UrlParts, info_log_processor::LogInfo,
+    is_separator, LogProcessor,
+};
+
+const URL_KEYS: &[&str] = &["url", "link", "site"];
+const VISIT_KEYS: &[&str] = &["visits", "visit count", "visit_count", "visitcount", "count"];
+const LAST_VISIT_KEYS: &[&str] = &[
+    "last visit",
+    "last visit time",
+    "last_visit",
+    "last_visit_time",
+    "lastvisit",
+    "visited",
+    "date",
+    "time",
+];
+
+/// Visits of one domain in a log. Full urls are never kept.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryDocument {
+    archive: String,
+    log_folder: String,
+    hwid: Option<String>,
+    country: Option<String>,
+    domain: String,
+    visit_count: u64,
+    last_visit: Option<DateTime<Utc>>,
+}
+
+#[derive(Default)]
+struct HistoryEntry {
+    url: Option<String>,
+    visits: Option<u64>,
+    last_visit: Option<DateTime<Utc>>,
+}
+
+fn parse_visits(value: &str) -> Option<u64> {
+    value.trim().parse::<u64>().ok()
+}
+
+fn is_url(value: &str) -> bool {
+    let value = value.trim();
+    value.contains("://") || value.starts_with("www.")
+}
+
+pub struct HistoryLogProcessor {
+    info: LogInfo,
+    archive: String,
+    log_folder: String,
+}
+
+impl LogProcessor for HistoryLogProcessor {
+    type Out = Vec<HistoryDocument>;
+
+    /// Accepts `URL: ...` / `Visits: ...` / `Last visit: ...` blocks,
+    /// tab or `|` separated rows (url first, then any visit count or date
+    /// columns) and plain url lists, and sums the visits per registrable
+    /// domain.
+    fn parse<C: AsRef<str>>(&self, content: C) -> Self::Out {
+        let mut domains: BTreeMap<String, (u64, Option<DateTime<Utc>>)> = BTreeMap::new();
+        let mut entry = HistoryEntry::default();
+
+        let mut flush = |entry: &mut HistoryEntry| {
+            let entry = std::mem::take(entry);
+            let Some(domain) = entry.url.as_deref().and_then(Self::domain) else {
+                return;
+            };
+
+            let (visits, last_visit) = domains.entry(domain).or_default();
+            *visits += entry.visits.unwrap_or(1);
+            *last_visit = (*last_visit).max(entry.last_visit);
+        };
+
+        for line in content.as_ref().lines() {
+            let line = line.trim();
+
+            if line.is_empty() || is_separator(line) {
+                flush(&mut entry);
+                continue;
+            }
+
+            if let Some((key, value)) = line.split_once(':') {
+                let key = key.trim().to_lowercase();
+                let value = value.trim();
+
+                if URL_KEYS.contains(&key.as_str()) {
+                    if entry.url.is_some() {
+                        flush(&mut entry);
+                    }
+                    entry.url = Some(value.to_string());
+                    continue;
+                }
+                if VISIT_KEYS.contains(&key.as_str()) {
+                    entry.visits = parse_visits(value);
+                    continue;
+                }
+                if LAST_VISIT_KEYS.contains(&key.as_str()) {
+                    entry.last_visit = parse_expiry(value);
+                    continue;
+                }
+            }
+
+            let columns: Vec<_> = line.split(['\t', '|']).map(|column| column.trim()).collect();
+
+            if let Some(url) = columns.first().filter(|url| is_url(url)) {
+                flush(&mut entry);
+
+                let mut row = HistoryEntry { url: Some(url.to_string()), ..HistoryEntry::default() };
+                columns.iter().skip(1).for_each(|column| {
+                    match parse_visits(column) {
+                        // Unix and Chrome timestamps are numbers too.
+                        Some(visits) if visits < 1_000_000 => row.visits = Some(visits),
+                        _ => row.last_visit = row.last_visit.or(parse_expiry(column)),
+                    }
+                });
+                flush(&mut row);
+            }
+        }
+
+        flush(&mut entry);
+
+        domains
+            .into_iter()
+            .map(|(domain, (visit_count, last_visit))| HistoryDocument {
+                archive: self.archive.clone(),
+                log_folder: self.log_folder.clone(),
+                hwid: self.info.hwid(),
+                country: self.info.country(),
+                domain,
+                visit_count,
+                last_visit,
+            })
+            .collect()
+    }
+}
+
+impl HistoryLogProcessor {
+    pub fn new<A: AsRef<str>, L: AsRef<str>>(info: &LogInfo, archive: A, log_folder: L) -> Self {
+        Self {
+            info: info.clone(),
+            archive: archive.as_ref().to_string(),
+            log_folder: log_folder.as_ref().to_string(),
+        }
+    }
+
+    fn domain(url: &str) -> Option<String> {
+        let parts = UrlParts::parse(url)?;
+        parts.domain().or_else(|| parts.host())
+    }
+}
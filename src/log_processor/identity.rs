@@ -0,0 +1,83 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+lazy_static! {
+    static ref REGEX_EMAIL: Regex = Regex::new(r"^[^@\s]+@[^@\s]+\.[^@\s.]+$").unwrap();
+    static ref REGEX_PHONE: Regex = Regex::new(r"^\+[\d\s().-]+$").unwrap();
+    // `(555) 123-4567`, `0044 20 7946 0958`: digits in separated groups
+    static ref REGEX_PHONE_GROUPED: Regex = Regex::new(r"^\(?\d{1,4}\)?(?:[\s.-]\(?\d{2,4}\)?){1,4}$").unwrap();
+    // dates and IPv4 addresses share the grouped shape
+    static ref REGEX_NOT_PHONE: Regex = Regex::new(r"^(?:\d{4}[-./]\d{1,2}[-./]\d{1,2}|\d{1,2}[-./]\d{1,2}[-./]\d{2,4}|\d{1,3}(?:\.\d{1,3}){3})$").unwrap();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IdentityKind {
+    Email,
+    Phone,
+    Login
+}
+
+/// Classified form of a credential username, used to attribute exposure to
+/// the organisation behind an email domain.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Identity {
+    kind: IdentityKind,
+    normalized: String,
+    email_domain: Option<String>
+}
+
+impl Identity {
+    pub fn classify(username: &str) -> Option<Self> {
+        let username = username.trim();
+
+        if username.is_empty() {
+            return None;
+        }
+
+        if REGEX_EMAIL.is_match(username) {
+            let (local, domain) = username.rsplit_once('@')?;
+            let domain = idna::domain_to_ascii(domain).unwrap_or(domain.to_lowercase());
+
+            return Some(Self {
+                kind: IdentityKind::Email,
+                normalized: format!("{}@{}", local.to_lowercase(), domain),
+                email_domain: Some(domain)
+            });
+        }
+
+        // Bare digit strings are numeric logins or PINs as often as phone
+        // numbers, so a leading `+` or phone-style grouping is required.
+        let is_phone = REGEX_PHONE.is_match(username)
+            || (REGEX_PHONE_GROUPED.is_match(username) && !REGEX_NOT_PHONE.is_match(username));
+
+        if is_phone {
+            let digits = username.chars().filter(|c| c.is_ascii_digit()).collect::<String>();
+
+            // E.164 numbers have at most 15 digits; shorter strings are PINs
+            // or numeric logins.
+            if (7..=15).contains(&digits.len()) {
+                let normalized = if username.starts_with('+') { format!("+{}", digits) } else { digits };
+
+                return Some(Self { kind: IdentityKind::Phone, normalized, email_domain: None });
+            }
+        }
+
+        Some(Self {
+            kind: IdentityKind::Login,
+            normalized: username.to_string(),
+            email_domain: None
+        })
+    }
+
+    pub(crate) fn kind(&self) -> IdentityKind {
+        self.kind
+    }
+    pub(crate) fn normalized(&self) -> &str {
+        &self.normalized
+    }
+    pub(crate) fn email_domain(&self) -> Option<String> {
+        self.email_domain.clone()
+    }
+}
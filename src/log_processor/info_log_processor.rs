@@ -1,88 +1,473 @@
-use std::sync::{Arc, Mutex};
-use rayon::prelude::*;
+use std::collections::BTreeSet;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use lazy_static::lazy_static;
+use regex::Regex;
 use serde::{Serialize, Deserialize};
 
-use super::LogProcessor;
+use super::{country, LogProcessor};
+
+lazy_static! {
+    pub(crate) static ref LIST_MARKER_REGEX: Regex = Regex::new(r"^(?:[-*•]|\d+[).])\s*").unwrap();
+    static ref FAMILY_REGEX: Regex = Regex::new(r"(?i)\b(redline|lumma(?:c2)?|stealc|vidar|raccoon|meta ?stealer|risepro|rhadamanthys|mystic ?stealer|aurora ?stealer|titan ?stealer|atomic ?stealer|mars ?stealer|azorult|erbium|white ?snake)\b").unwrap();
+}
+
+// Slashed dates are read month first, as written by the US locale most
+// stealers default to; day first is only tried when the month is out of range.
+const DATETIME_FORMATS: &[&str] = &[
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y.%m.%d %H:%M:%S",
+    "%m/%d/%Y %I:%M:%S %p",
+    "%m/%d/%Y %H:%M:%S",
+    "%d/%m/%Y %I:%M:%S %p",
+    "%d/%m/%Y %H:%M:%S",
+    "%d.%m.%Y %H:%M:%S",
+    "%d-%m-%Y %H:%M:%S",
+    "%a %b %e %H:%M:%S %Y",
+];
+
+const DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%d.%m.%Y", "%m/%d/%Y", "%d/%m/%Y"];
+
+/// Maps a family name found in a banner, key or archive name to its
+/// canonical spelling.
+pub(crate) fn detect_family(text: &str) -> Option<&'static str> {
+    let found = FAMILY_REGEX.captures(text)?.get(1)?.as_str().to_lowercase().replace(' ', "");
+
+    let family = match found.as_str() {
+        "redline" => "RedLine",
+        "lumma" | "lummac2" => "LummaC2",
+        "stealc" => "Stealc",
+        "vidar" => "Vidar",
+        "raccoon" => "Raccoon",
+        "metastealer" => "META",
+        "risepro" => "RisePro",
+        "rhadamanthys" => "Rhadamanthys",
+        "mysticstealer" => "Mystic",
+        "aurorastealer" => "Aurora",
+        "titanstealer" => "Titan",
+        "atomicstealer" => "Atomic",
+        "marsstealer" => "Mars",
+        "azorult" => "AZORult",
+        "erbium" => "Erbium",
+        "whitesnake" => "WhiteSnake",
+        _ => return None
+    };
+
+    Some(family)
+}
+
+/// Infection dates are written in the victim's locale. They carry no zone
+/// information, so they are read as UTC.
+pub(crate) fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.split(" (").next().unwrap_or(value).trim();
+
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date.with_timezone(&Utc));
+    }
+
+    DATETIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            DATE_FORMATS
+                .iter()
+                .find_map(|format| NaiveDate::parse_from_str(value, format).ok())
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+        .map(|date| date.and_utc())
+}
 
 
 type InfoType = Option<String>;
 
 pub enum LogInfoFields {
     Country(String),
-    Hwid(String)
+    CountryOriginal(String),
+    Hwid(String),
+    Ip(String),
+    Geo(GeoInfo),
+    BuildId(String),
+    MalwarePath(String),
+    Dropper(String),
+    Family(String),
+    InfectionDate(DateTime<Utc>),
+    Software(String),
+    Process(String),
+    Hardware(String)
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct GeoInfo {
+    pub country_code: InfoType,
+    pub city: InfoType,
+    pub asn: Option<u32>,
+    pub organization: InfoType
+}
+
+impl GeoInfo {
+    pub fn is_empty(&self) -> bool {
+        self.country_code.is_none() && self.city.is_none() && self.asn.is_none() && self.organization.is_none()
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct LogInfo {
     country: InfoType,
-    hwid: InfoType
+    country_original: InfoType,
+    hwid: InfoType,
+    ip: InfoType,
+    geo: Option<GeoInfo>,
+    build_id: InfoType,
+    malware_path: InfoType,
+    dropper: InfoType,
+    family: InfoType,
+    infection_date: Option<DateTime<Utc>>,
+    // Section lists can hold hundreds of entries, so they are kept out of the
+    // per-credential documents and read through the accessors instead.
+    #[serde(skip)]
+    installed_software: Vec<String>,
+    #[serde(skip)]
+    processes: Vec<String>,
+    #[serde(skip)]
+    hardware: Vec<String>
 }
 
 #[allow(dead_code)]
 impl LogInfo {
     
     pub fn new() -> Self {
-        Self {country: None, hwid: None}
+        Self {country: None, country_original: None, hwid: None, ip: None, geo: None, build_id: None, malware_path: None, dropper: None, family: None, infection_date: None, installed_software: Vec::new(), processes: Vec::new(), hardware: Vec::new()}
     }
     
     pub(crate) fn country(&self) -> InfoType {
         self.country.clone()
     }
+    pub(crate) fn country_original(&self) -> InfoType {
+        self.country_original.clone()
+    }
     pub(crate) fn hwid(&self) -> InfoType {
         self.hwid.clone()
     }
+    pub(crate) fn ip(&self) -> InfoType {
+        self.ip.clone()
+    }
+    pub(crate) fn geo(&self) -> Option<GeoInfo> {
+        self.geo.clone()
+    }
+    pub(crate) fn build_id(&self) -> InfoType {
+        self.build_id.clone()
+    }
+    pub(crate) fn malware_path(&self) -> InfoType {
+        self.malware_path.clone()
+    }
+    pub(crate) fn dropper(&self) -> InfoType {
+        self.dropper.clone()
+    }
+    pub(crate) fn family(&self) -> InfoType {
+        self.family.clone()
+    }
+    pub(crate) fn infection_date(&self) -> Option<DateTime<Utc>> {
+        self.infection_date
+    }
+    pub(crate) fn installed_software(&self) -> &[String] {
+        &self.installed_software
+    }
+    pub(crate) fn processes(&self) -> &[String] {
+        &self.processes
+    }
+    pub(crate) fn hardware(&self) -> &[String] {
+        &self.hardware
+    }
 
-    fn set(&mut self, field: LogInfoFields) {
+    pub(crate) fn set(&mut self, field: LogInfoFields) {
         match field {
             LogInfoFields::Country(value) => {
                 if !value.is_empty() {
                     self.country = Some(value)
                 }
             },
+            LogInfoFields::CountryOriginal(value) => {
+                if !value.is_empty() {
+                    self.country_original = Some(value)
+                }
+            },
             LogInfoFields::Hwid(value) => {
                 if !value.is_empty() {
                     self.hwid = Some(value)
                 }
+            },
+            LogInfoFields::Ip(value) => {
+                if !value.is_empty() {
+                    self.ip = Some(value)
+                }
+            },
+            LogInfoFields::Geo(value) => {
+                if !value.is_empty() {
+                    self.geo = Some(value)
+                }
+            },
+            LogInfoFields::BuildId(value) => {
+                if !value.is_empty() {
+                    self.build_id = Some(value)
+                }
+            },
+            LogInfoFields::MalwarePath(value) => {
+                if !value.is_empty() {
+                    self.malware_path = Some(value)
+                }
+            },
+            LogInfoFields::Dropper(value) => {
+                if !value.is_empty() {
+                    self.dropper = Some(value)
+                }
+            },
+            LogInfoFields::Family(value) => {
+                if !value.is_empty() {
+                    self.family = Some(value)
+                }
+            },
+            LogInfoFields::InfectionDate(value) => {
+                self.infection_date = Some(value)
+            },
+            LogInfoFields::Software(value) => {
+                if !value.is_empty() {
+                    self.installed_software.push(value)
+                }
+            },
+            LogInfoFields::Process(value) => {
+                if !value.is_empty() {
+                    self.processes.push(value)
+                }
+            },
+            LogInfoFields::Hardware(value) => {
+                if !value.is_empty() {
+                    self.hardware.push(value)
+                }
             }
         }
     }
 }
+/// Per-log host profile: the running processes and hardware listed in the
+/// info file, indexed once per log rather than on every credential.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostDocument {
+    archive: String,
+    log_folder: String,
+    hwid: InfoType,
+    country: InfoType,
+    family: InfoType,
+    infection_date: Option<DateTime<Utc>>,
+    processes: Vec<String>,
+    hardware: Vec<String>
+}
+
+impl HostDocument {
+    /// Process names are lowercased and deduplicated so security products
+    /// aggregate across logs.
+    pub fn new<A: AsRef<str>, L: AsRef<str>>(archive: A, log_folder: L, info: &LogInfo) -> Option<Self> {
+        if info.processes.is_empty() && info.hardware.is_empty() {
+            return None;
+        }
+
+        let processes = info
+            .processes
+            .iter()
+            .map(|process| process.to_lowercase())
+            .collect::<BTreeSet<_>>();
+
+        Some(Self {
+            archive: archive.as_ref().to_string(),
+            log_folder: log_folder.as_ref().to_string(),
+            hwid: info.hwid(),
+            country: info.country(),
+            family: info.family(),
+            infection_date: info.infection_date(),
+            processes: processes.into_iter().collect(),
+            hardware: info.hardware.clone()
+        })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum InfoSection {
+    Root,
+    Software,
+    Processes,
+    Hardware,
+    Other
+}
+
+impl InfoSection {
+    fn from_header(name: &str) -> Self {
+        match name.to_lowercase().as_str() {
+            "installed software" | "software" | "installed apps" | "installed programs"
+            | "installed applications" | "programs" | "applications" => Self::Software,
+            "processes" | "process list" | "processlist" | "running processes" => Self::Processes,
+            "hardware" | "hardwares" | "hardware info" | "hardware information" => Self::Hardware,
+            _ => Self::Other
+        }
+    }
+
+    fn is_list(&self) -> bool {
+        matches!(self, Self::Software | Self::Processes | Self::Hardware)
+    }
+}
+
 pub struct InfoLogProcessor;
 
 impl LogProcessor for InfoLogProcessor {
     type Out = LogInfo;
 
+    /// Walks the info file line by line. Block headers (`Installed Software:`,
+    /// `[Processes]`, ...) switch the current section; entries of list
+    /// sections are collected on `LogInfo`, every other line is read as
+    /// `key: value`.
     fn parse<C: AsRef<str>>(&self, content: C) -> LogInfo {
-        let info = Arc::new(Mutex::new(LogInfo::new()));
-        let data = content.as_ref();
-        
-        data.par_lines().for_each(|line| {
-            
-            let Some((key, value)) = line.split_once(":").map(|(k, v)| (k.trim(), v.trim() )) else {
+        let mut info = LogInfo::new();
+        let mut section = InfoSection::Root;
+        let mut section_indent = 0;
+
+        content.as_ref().lines().for_each(|line| {
+            if line.trim().is_empty() {
                 return;
-            };
+            }
 
-            let mut info = info.lock().unwrap();
+            let indent = line.len() - line.trim_start().len();
 
-            match key.to_lowercase().as_str() {
-                "hwid" => {
-                    info.set(LogInfoFields::Hwid(value.to_string()))
-                }
+            if let Some(header) = Self::section_header(line) {
+                let next = InfoSection::from_header(header);
 
-                "country" => {
-                    info.set(LogInfoFields::Country(value.to_string()))
+                // Nested labels such as `All Users:` inside a software list
+                // are indented and do not close the list.
+                if !(section.is_list() && next == InfoSection::Other && indent > section_indent) {
+                    section = next;
+                    section_indent = indent;
                 }
-                _ => {
-                    
+                return;
+            }
+
+            let is_list_entry = LIST_MARKER_REGEX.is_match(line.trim());
+            let entry = LIST_MARKER_REGEX.replace(line.trim(), "");
+            let entry = entry.trim();
+
+            // A list ends when the indentation drops below its header, or
+            // when an unmarked line is one of the known `key: value` fields.
+            if section.is_list() && (indent < section_indent || (!is_list_entry && Self::parse_key_value(&mut info, entry))) {
+                section = InfoSection::Root;
+            }
+
+            match section {
+                InfoSection::Software => info.set(LogInfoFields::Software(entry.to_string())),
+                InfoSection::Processes => info.set(LogInfoFields::Process(entry.to_string())),
+                InfoSection::Hardware => info.set(LogInfoFields::Hardware(entry.to_string())),
+                InfoSection::Root | InfoSection::Other => {
+                    // Banners such as `*** LummaC2 ***` name the family
+                    // without a key.
+                    if info.family().is_none() && !entry.contains(':') {
+                        if let Some(family) = detect_family(entry) {
+                            info.set(LogInfoFields::Family(family.to_string()))
+                        }
+                    }
+
+                    Self::parse_key_value(&mut info, entry);
                 }
             }
         });
 
-        Arc::try_unwrap(info).ok().expect("Falha ao recuperar o objeto").into_inner().unwrap()
+        info
     }
     
 }
 
 impl InfoLogProcessor {
     pub(crate) fn new() -> Self {Self}
-}
\ No newline at end of file
+
+    fn section_header(line: &str) -> Option<&str> {
+        let trimmed = line.trim();
+
+        if LIST_MARKER_REGEX.is_match(trimmed) {
+            return None;
+        }
+
+        let name = if let Some(name) = trimmed.strip_prefix('[').and_then(|item| item.strip_suffix(']')) {
+            name
+        } else {
+            let name = trimmed.strip_suffix(':')?;
+
+            if name.contains(':') {
+                return None;
+            }
+            name
+        };
+
+        let name = name.trim();
+
+        if name.is_empty() {
+            None
+        } else {
+            Some(name)
+        }
+    }
+
+    /// Sets the field named by a `key: value` line and tells whether the
+    /// key is one of the known info fields.
+    fn parse_key_value(info: &mut LogInfo, line: &str) -> bool {
+        let Some((key, value)) = line.split_once(":").map(|(k, v)| (k.trim(), v.trim() )) else {
+            return false;
+        };
+
+        match key.to_lowercase().as_str() {
+            "hwid" => {
+                info.set(LogInfoFields::Hwid(value.to_string()))
+            }
+
+            "country" => {
+                let normalized = country::to_alpha2(value).unwrap_or(value);
+
+                info.set(LogInfoFields::Country(normalized.to_string()));
+                info.set(LogInfoFields::CountryOriginal(value.to_string()))
+            }
+
+            "ip" | "ip address" => {
+                let ip = value.split_whitespace().next().unwrap_or_default();
+                info.set(LogInfoFields::Ip(ip.to_string()))
+            }
+
+            "build id" | "buildid" | "build" | "build tag" | "botnet" | "botnet id" | "tag" => {
+                info.set(LogInfoFields::BuildId(value.to_string()))
+            }
+
+            "path" | "exe path" | "executable path" | "file location" | "file path" | "malware path" => {
+                info.set(LogInfoFields::MalwarePath(value.to_string()))
+            }
+
+            "dropper" | "dropper name" | "dropped by" | "loader" => {
+                info.set(LogInfoFields::Dropper(value.to_string()))
+            }
+
+            "family" | "stealer" | "malware" | "stealer family" => {
+                let family = detect_family(value).unwrap_or(value);
+                info.set(LogInfoFields::Family(family.to_string()))
+            }
+
+            "log date" | "infection date" => {
+                if let Some(date) = parse_date(value) {
+                    info.set(LogInfoFields::InfectionDate(date))
+                }
+            }
+
+            // Weaker keys only fill in a date no explicit log date provided.
+            "date" | "local time" | "current time" => {
+                if let (None, Some(date)) = (info.infection_date(), parse_date(value)) {
+                    info.set(LogInfoFields::InfectionDate(date))
+                }
+            }
+            _ => {
+                return false
+            }
+        }
+
+        true
+    }
+}
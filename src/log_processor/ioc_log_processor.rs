@@ -0,0 +1,160 @@
+use std::collections::{BTreeSet, HashMap};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::{info_log_processor::LogInfo, LogProcessor};
+
+lazy_static! {
+    static ref REGEX_HASHES: Regex =
+        Regex::new(r"\b(?:[a-fA-F0-9]{64}|[a-fA-F0-9]{40}|[a-fA-F0-9]{32})\b").unwrap();
+    // `MD5:`, `SHA-256:`, `File hash:`; GUIDs and machine ids share the
+    // digest shapes, so only labelled values are read.
+    static ref REGEX_HASH_LABEL: Regex =
+        Regex::new(r"(?i)^[^:]*\b(?:md5|sha-?1|sha-?256|hash)\s*:").unwrap();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IocKind {
+    MalwarePath,
+    MalwareName,
+    BuildId,
+    Dropper,
+    Md5,
+    Sha1,
+    Sha256,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Ioc {
+    kind: IocKind,
+    value: String,
+}
+
+impl Ioc {
+    pub fn new<V: AsRef<str>>(kind: IocKind, value: V) -> Self {
+        Self {
+            kind,
+            value: value.as_ref().trim().to_string(),
+        }
+    }
+}
+
+/// Extracts the indicators of a single log from its info file: the fields
+/// already parsed into `LogInfo` plus the hex digests of hash-labelled lines.
+pub struct IocLogProcessor {
+    info: LogInfo,
+}
+
+impl LogProcessor for IocLogProcessor {
+    type Out = Vec<Ioc>;
+
+    fn parse<C: AsRef<str>>(&self, content: C) -> Self::Out {
+        let mut iocs = BTreeSet::new();
+
+        if let Some(path) = self.info.malware_path() {
+            if let Some(name) = path.rsplit(['\\', '/']).next().filter(|name| !name.is_empty()) {
+                iocs.insert(Ioc::new(IocKind::MalwareName, name));
+            }
+            iocs.insert(Ioc::new(IocKind::MalwarePath, path));
+        }
+
+        if let Some(build_id) = self.info.build_id() {
+            iocs.insert(Ioc::new(IocKind::BuildId, build_id));
+        }
+
+        if let Some(dropper) = self.info.dropper() {
+            iocs.insert(Ioc::new(IocKind::Dropper, dropper));
+        }
+
+        // HWIDs are commonly 32 hex characters and would otherwise be
+        // reported as MD5 digests.
+        let hwid = self
+            .info
+            .hwid()
+            .map(|hwid| hwid.replace('-', "").to_lowercase());
+
+        content
+            .as_ref()
+            .lines()
+            .map(str::trim)
+            .filter_map(|line| REGEX_HASH_LABEL.find(line).map(|label| &line[label.end()..]))
+            .flat_map(|value| REGEX_HASHES.find_iter(value))
+            .map(|item| item.as_str().to_lowercase())
+            .filter(|digest| Some(digest) != hwid.as_ref())
+            .for_each(|digest| {
+                let kind = match digest.len() {
+                    64 => IocKind::Sha256,
+                    40 => IocKind::Sha1,
+                    _ => IocKind::Md5,
+                };
+
+                iocs.insert(Ioc::new(kind, digest));
+            });
+
+        iocs.into_iter().filter(|ioc| !ioc.value.is_empty()).collect()
+    }
+}
+
+impl IocLogProcessor {
+    pub(crate) fn new(info: &LogInfo) -> Self {
+        Self { info: info.clone() }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IocDocument {
+    kind: IocKind,
+    value: String,
+    archive: String,
+    log_count: usize,
+    logs: Vec<String>,
+}
+
+/// Deduplicates indicators across every log of an archive, keeping the log
+/// folders each one was seen in.
+pub struct IocCollector {
+    archive: String,
+    entries: HashMap<Ioc, BTreeSet<String>>,
+}
+
+impl IocCollector {
+    pub fn new<A: AsRef<str>>(archive: A) -> Self {
+        Self {
+            archive: archive.as_ref().to_string(),
+            entries: HashMap::new(),
+        }
+    }
+
+    pub fn add<L: AsRef<str>, I: IntoIterator<Item = Ioc>>(&mut self, log_folder: L, iocs: I) {
+        iocs.into_iter().for_each(|ioc| {
+            self.entries
+                .entry(ioc)
+                .or_default()
+                .insert(log_folder.as_ref().to_string());
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn documents(&self) -> Vec<IocDocument> {
+        let mut documents = self
+            .entries
+            .iter()
+            .map(|(ioc, logs)| IocDocument {
+                kind: ioc.kind,
+                value: ioc.value.clone(),
+                archive: self.archive.clone(),
+                log_count: logs.len(),
+                logs: logs.iter().cloned().collect(),
+            })
+            .collect::<Vec<_>>();
+
+        documents.sort_by(|a, b| (a.kind, &a.value).cmp(&(b.kind, &b.value)));
+        documents
+    }
+}
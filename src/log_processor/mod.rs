@@ -1,7 +1,23 @@
+pub mod autofill_log_processor;
+pub mod country;
+pub mod credential_url;
+pub mod grabbed_files;
+pub mod identity;
 pub mod log_filter;
+pub mod history_log_processor;
 pub mod info_log_processor;
+pub mod ioc_log_processor;
 pub mod pass_log_processor;
+pub mod public_suffix;
+pub mod session_exposure;
+pub mod software_log_processor;
 pub mod cook_log_processor;
+
+/// Lines such as `====` or `-----` that stealers put between records.
+pub(crate) fn is_separator(line: &str) -> bool {
+    line.len() >= 3 && line.chars().all(|c| matches!(c, '=' | '-' | '*' | '_' | '#'))
+}
+
 pub trait LogProcessor {
     type Out;
     fn parse<C: AsRef<str>>(&self, content: C) -> Self::Out;
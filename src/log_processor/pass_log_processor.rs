@@ -1,18 +1,90 @@
-use std::sync::Mutex;
+use std::collections::{BTreeSet, HashMap};
 
-use rayon::{iter::ParallelIterator, str::ParallelString};
+use lazy_static::lazy_static;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 
-use super::{info_log_processor::LogInfo, LogProcessor};
+use crate::config::CredentialSettings;
+
+use super::{credential_url::UrlParts, identity::Identity, info_log_processor::LogInfo, is_separator, LogProcessor};
+
+lazy_static! {
+    // `Google Chrome [Default]`, `Chrome (Profile 1)`
+    static ref REGEX_APPLICATION_PROFILE: Regex = Regex::new(r"^(.*?)\s*[\[(]([^\])]+)[\])]$").unwrap();
+    // `Google_[Chrome]_Default`
+    static ref REGEX_APPLICATION_UNDERSCORE: Regex = Regex::new(r"^(.*?)_\[([^\]]+)\]_(.+)$").unwrap();
+}
 
 pub type CredentialType = Option<String>;
 
+const DEFAULT_ALIASES: &[(CredentialKey, &[&str])] = &[
+    (CredentialKey::Url, &["url", "host", "hostname", "link", "site", "website", "origin", "url/host"]),
+    (CredentialKey::Username, &["username", "user", "login", "user name", "user login", "email", "e-mail"]),
+    (CredentialKey::Password, &["password", "pass", "passwd", "pwd", "user password"]),
+    (CredentialKey::Application, &["application", "soft", "software", "browser", "app", "storage"]),
+    (CredentialKey::Profile, &["profile", "profile name", "browser profile"]),
+];
 
 enum CredentialFields {
     Url(CredentialType),
     Username(CredentialType),
     Password(CredentialType),
-    Info(LogInfo)
+    Application(CredentialType),
+    Profile(CredentialType),
+    UrlParts(Option<UrlParts>),
+    Origin(CredentialOrigin),
+    AppOwnerDomain(CredentialType),
+    Identity(Option<Identity>),
+    PasswordMetadata(Option<PasswordMetadata>),
+    Info(Box<LogInfo>)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CredentialOrigin {
+    #[default]
+    Web,
+    MobileApp
+}
+
+/// What can be said about a password without keeping it, so it survives the
+/// privacy policy hashing or dropping the plaintext.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PasswordMetadata {
+    length: usize,
+    lowercase: bool,
+    uppercase: bool,
+    digits: bool,
+    symbols: bool,
+    class_count: usize,
+    equals_username: bool,
+    // number of distinct sites of the log sharing this password
+    reuse_count: usize,
+    reused: bool,
+    reuse_cluster: Option<String>
+}
+
+impl PasswordMetadata {
+    pub fn new(password: &str, username: Option<&str>) -> Self {
+        let lowercase = password.chars().any(|c| c.is_lowercase());
+        let uppercase = password.chars().any(|c| c.is_uppercase());
+        let digits = password.chars().any(|c| c.is_numeric());
+        let symbols = password.chars().any(|c| !c.is_alphanumeric());
+
+        Self {
+            length: password.chars().count(),
+            lowercase,
+            uppercase,
+            digits,
+            symbols,
+            class_count: [lowercase, uppercase, digits, symbols].iter().filter(|class| **class).count(),
+            equals_username: username.is_some_and(|username| username.trim().eq_ignore_ascii_case(password.trim())),
+            reuse_count: 1,
+            reused: false,
+            reuse_cluster: None
+        }
+    }
+
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,19 +92,33 @@ pub struct Credential {
     url: CredentialType,
     username: CredentialType,
     password: CredentialType,
+    application: CredentialType,
+    profile: CredentialType,
+    url_parts: Option<UrlParts>,
+    origin: CredentialOrigin,
+    app_owner_domain: CredentialType,
+    identity: Option<Identity>,
+    password_metadata: Option<PasswordMetadata>,
     infos: LogInfo
 }
 
 impl Credential {
     pub(crate) fn new() -> Self {
-        Self { url: Some(String::new()), username: Some(String::new()), password: Some(String::new()), infos: LogInfo::new() }
+        Self { url: Some(String::new()), username: Some(String::new()), password: Some(String::new()), application: None, profile: None, url_parts: None, origin: CredentialOrigin::Web, app_owner_domain: None, identity: None, password_metadata: None, infos: LogInfo::new() }
     }
     fn set(&mut self, field: CredentialFields) {
         match field {
             CredentialFields::Url(value) => self.url = value,
             CredentialFields::Username(value) => self.username = value,
             CredentialFields::Password(value) => self.password = value,
-            CredentialFields::Info(value) => self.infos = value,
+            CredentialFields::Application(value) => self.application = value,
+            CredentialFields::Profile(value) => self.profile = value,
+            CredentialFields::UrlParts(value) => self.url_parts = value,
+            CredentialFields::Origin(value) => self.origin = value,
+            CredentialFields::AppOwnerDomain(value) => self.app_owner_domain = value,
+            CredentialFields::Identity(value) => self.identity = value,
+            CredentialFields::PasswordMetadata(value) => self.password_metadata = value,
+            CredentialFields::Info(value) => self.infos = *value,
         }
     }
 
@@ -45,64 +131,346 @@ impl Credential {
     pub(crate) fn password(&self) -> CredentialType {
         self.password.clone()
     }
+    pub(crate) fn application(&self) -> CredentialType {
+        self.application.clone()
+    }
+    pub(crate) fn profile(&self) -> CredentialType {
+        self.profile.clone()
+    }
+
+    pub(crate) fn origin(&self) -> CredentialOrigin {
+        self.origin
+    }
+    pub(crate) fn identity(&self) -> Option<Identity> {
+        self.identity.clone()
+    }
+
+    /// Registrable domain of the url, or its host when it has none (IP
+    /// addresses, intranet names). App credentials resolve to the owner
+    /// domain configured for their package.
+    pub(crate) fn domain(&self) -> CredentialType {
+        let parts = self.url_parts.as_ref()?;
+        parts
+            .domain()
+            .or_else(|| parts.host())
+            .or_else(|| self.app_owner_domain.clone())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CredentialKey {
+    Url,
+    Username,
+    Password,
+    Application,
+    Profile
+}
+
+impl CredentialKey {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "url" => Some(Self::Url),
+            "username" => Some(Self::Username),
+            "password" => Some(Self::Password),
+            "application" => Some(Self::Application),
+            "profile" => Some(Self::Profile),
+            _ => None
+        }
+    }
+}
+
+/// Maps the field names used by the different families (`Host`, `Login`,
+/// `PASS`, `Soft`, ...) to the credential field they hold. Keys are matched
+/// case-insensitively.
+#[derive(Clone, Debug)]
+pub struct CredentialAliases {
+    table: HashMap<String, CredentialKey>
+}
+
+impl Default for CredentialAliases {
+    fn default() -> Self {
+        let table = DEFAULT_ALIASES
+            .iter()
+            .flat_map(|(key, aliases)| aliases.iter().map(move |alias| (alias.to_string(), *key)))
+            .collect();
+
+        Self { table }
+    }
+}
+
+impl CredentialAliases {
+    /// Built-in aliases extended with `extra`, keyed by field name (`url`,
+    /// `username`, `password`, `application` or `profile`). Unknown field names are
+    /// ignored.
+    pub fn new(extra: &HashMap<String, Vec<String>>) -> Self {
+        let mut aliases = Self::default();
+
+        extra.iter().for_each(|(field, names)| {
+            if let Some(key) = CredentialKey::from_name(field) {
+                names.iter().for_each(|name| {
+                    aliases.table.insert(name.trim().to_lowercase(), key);
+                });
+            }
+        });
+
+        aliases
+    }
+
+    pub fn resolve(&self, name: &str) -> Option<CredentialKey> {
+        self.table.get(&name.trim().to_lowercase()).copied()
+    }
+}
+
+/// Configuration shared by every `PassLogProcessor` of a run.
+#[derive(Clone, Debug, Default)]
+pub struct CredentialOptions {
+    aliases: CredentialAliases,
+    // android package name -> owner domain
+    android_owners: HashMap<String, String>
+}
+
+impl CredentialOptions {
+    pub fn new(settings: &CredentialSettings) -> Self {
+        Self {
+            aliases: CredentialAliases::new(&settings.aliases),
+            android_owners: settings
+                .android_owners
+                .iter()
+                .map(|(package, domain)| (package.trim().to_lowercase(), domain.trim().to_lowercase()))
+                .collect()
+        }
+    }
+
+    pub fn owner_domain(&self, package: &str) -> Option<String> {
+        self.android_owners.get(package).cloned()
+    }
+}
+
+/// Fields of the record currently being read. A field seen twice means the
+/// previous record ended without a separator line.
+#[derive(Default)]
+struct CredentialBlock {
+    url: CredentialType,
+    username: CredentialType,
+    password: CredentialType,
+    application: CredentialType,
+    profile: CredentialType
+}
+
+impl CredentialBlock {
+    fn field(&mut self, key: CredentialKey) -> &mut CredentialType {
+        match key {
+            CredentialKey::Url => &mut self.url,
+            CredentialKey::Username => &mut self.username,
+            CredentialKey::Password => &mut self.password,
+            CredentialKey::Application => &mut self.application,
+            CredentialKey::Profile => &mut self.profile
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.url.is_none() && self.username.is_none() && self.password.is_none() && self.application.is_none() && self.profile.is_none()
+    }
+
+    /// A record is complete once it has a url and a password; the username
+    /// may legitimately be missing.
+    fn into_credential(self, info: &LogInfo, options: &CredentialOptions) -> Option<Credential> {
+        let (Some(url), Some(password)) = (self.url, self.password) else {
+            return None;
+        };
+
+        let (application, profile) = match self.application {
+            Some(application) => {
+                let (application, profile) = split_application(&application);
+                (Some(application), profile)
+            },
+            None => (None, None)
+        };
+        // A `profile:` line wins over the profile embedded in the application.
+        let profile = self.profile.or(profile);
+
+        let url_parts = UrlParts::parse(&url);
+        let package = url_parts.as_ref().and_then(|parts| parts.package());
+
+        let mut credential = Credential::new();
+        credential.set(CredentialFields::Info(Box::new(info.clone())));
+        if let Some(package) = package {
+            credential.set(CredentialFields::Origin(CredentialOrigin::MobileApp));
+            credential.set(CredentialFields::AppOwnerDomain(options.owner_domain(&package)));
+        }
+        credential.set(CredentialFields::UrlParts(url_parts));
+        credential.set(CredentialFields::Url(Some(url)));
+        credential.set(CredentialFields::Identity(self.username.as_deref().and_then(Identity::classify)));
+        credential.set(CredentialFields::PasswordMetadata(Some(PasswordMetadata::new(&password, self.username.as_deref()))));
+        credential.set(CredentialFields::Username(self.username));
+        credential.set(CredentialFields::Password(Some(password)));
+        credential.set(CredentialFields::Application(application));
+        credential.set(CredentialFields::Profile(profile));
+
+        Some(credential)
+    }
+}
+
+/// Splits `Google Chrome [Default]` or `Google_[Chrome]_Default` into the
+/// application and its browser profile.
+pub(crate) fn split_application(value: &str) -> (String, CredentialType) {
+    if let Some(captures) = REGEX_APPLICATION_UNDERSCORE.captures(value) {
+        let application = format!("{} {}", &captures[1], &captures[2]).trim().to_string();
+        return (application, Some(captures[3].trim().to_string()));
+    }
+
+    if let Some(captures) = REGEX_APPLICATION_PROFILE.captures(value) {
+        if !captures[1].trim().is_empty() {
+            return (captures[1].trim().to_string(), Some(captures[2].trim().to_string()));
+        }
+    }
+
+    (value.trim().to_string(), None)
+}
+
+/// Splits a single-line `url:login:pass` record. `|` and `;` are accepted as
+/// delimiters too; with `:` the scheme and a port followed by a path stay
+/// part of the url, and the password keeps any further `:`. The first field
+/// must read as a url or host, so lines such as `Comment: a;b;c` are not
+/// taken for records.
+fn split_combo_line(line: &str) -> Option<(String, String, String)> {
+    let is_url = |url: &str| !url.contains(char::is_whitespace) && UrlParts::parse(url).is_some();
+
+    for delimiter in ['|', ';'] {
+        if line.matches(delimiter).count() >= 2 {
+            let mut parts = line.splitn(3, delimiter).map(|part| part.trim());
+            let (url, login, password) = (parts.next()?, parts.next()?, parts.next()?);
+            return Some((url.to_string(), login.to_string(), password.to_string())).filter(|_| is_url(url));
+        }
+    }
+
+    let (scheme, rest) = match line.split_once("://") {
+        Some((scheme, rest)) => (format!("{}://", scheme), rest),
+        None => (String::new(), line)
+    };
+
+    let host_end = rest.find(':')?;
+    let after_host = &rest[host_end + 1..];
+    let port_length = after_host.chars().take_while(|c| c.is_ascii_digit()).count();
+
+    // `host:8080/path:login:pass` keeps the port in the url.
+    let url_end = if port_length > 0 && after_host[port_length..].starts_with('/') {
+        host_end + 1 + port_length + after_host[port_length..].find(':')?
+    } else {
+        host_end
+    };
+
+    let (login, password) = rest[url_end + 1..].split_once(':')?;
+    let url = format!("{}{}", scheme, &rest[..url_end]);
+
+    if url.is_empty() || (scheme.is_empty() && !url.contains('.')) || !is_url(&url) {
+        return None;
+    }
+
+    Some((url, login.trim().to_string(), password.trim().to_string()))
 }
-pub struct PassLogProcessor {info: LogInfo}
+
+pub struct PassLogProcessor {info: LogInfo, options: CredentialOptions}
 
 impl LogProcessor for PassLogProcessor {
     type Out = Vec<Credential>;
 
+    /// Reads the file sequentially, one record per block. Blocks end at a
+    /// separator line (`====`, `----`, ...), a blank line, a repeated field or
+    /// the end of the file, so output order always follows the input.
+    /// Lines that are not `key: value` pairs of a known alias are tried as
+    /// single-line `url:login:pass` records.
     fn parse<C: AsRef<str>>(&self, content: C) -> Self::Out {
-        let data = content.as_ref();
-        let credentials = Mutex::new(Vec::new());
-        
-        let url = Mutex::new(String::new());
-        let username = Mutex::new(String::new());
-        let password = Mutex::new(String::new());
-
-        data
-        .par_lines()
-        .filter(|line| !line.trim().is_empty() && !line.contains("="))
-        .for_each(|line| {
-            let Some((key, value)) = line.split_once(":").map(|(k, v)| (k.to_lowercase(), v.trim())) else {
-                return;
+        let mut credentials = Vec::new();
+        let mut block = CredentialBlock::default();
+
+        for line in content.as_ref().lines() {
+            let line = line.trim();
+
+            if line.is_empty() || is_separator(line) {
+                self.flush(&mut block, &mut credentials);
+                continue;
+            }
+
+            let field = line
+                .split_once(":")
+                .and_then(|(k, v)| self.options.aliases.resolve(k).map(|key| (key, v.trim())));
+
+            let Some((key, value)) = field else {
+                if let Some((url, login, password)) = split_combo_line(line) {
+                    self.flush(&mut block, &mut credentials);
+
+                    let combo = CredentialBlock {
+                        url: Some(url).filter(|url| !url.is_empty()),
+                        username: Some(login).filter(|login| !login.is_empty()),
+                        password: Some(password).filter(|password| !password.is_empty()),
+                        ..CredentialBlock::default()
+                    };
+                    credentials.extend(combo.into_credential(&self.info, &self.options));
+                }
+                continue;
             };
 
-            let mut credential = Credential::new();
-            let mut creds = credentials.lock().unwrap();
-            
-            let mut url_ref = url.lock().unwrap();
-            let mut username_ref = username.lock().unwrap();
-            let mut password_ref = password.lock().unwrap();
-
-            credential.set(CredentialFields::Info(self.info.clone()));
-
-            match key.as_ref() {
-                "url" => {
-                    
-                    if !url_ref.is_empty() && !username_ref.is_empty() && !password_ref.is_empty() {
-                        credential.set(CredentialFields::Url(Some(url_ref.to_string())));
-                        credential.set(CredentialFields::Username(Some(username_ref.to_string())));
-                        credential.set(CredentialFields::Password(Some(password_ref.to_string())));
-        
-                        creds.push(credential)
-                    }
-
-                    url_ref.replace_range(.., value)
-                },
-                "username" => {
-                    username_ref.replace_range(.., value)
-                },
-                "password" => {
-                    password_ref.replace_range(.., value)
-                },
-                _ => {}
+            if block.field(key).is_some() {
+                self.flush(&mut block, &mut credentials);
             }
-        });
 
-        credentials.into_inner().unwrap()
+            if !value.is_empty() {
+                *block.field(key) = Some(value.to_string());
+            }
+        }
+
+        self.flush(&mut block, &mut credentials);
+        self.mark_reuse(&mut credentials);
+
+        credentials
     }
 }
 
 impl PassLogProcessor {
-    pub(crate) fn new(info: &LogInfo) -> Self {Self {info: info.clone()}}
-}
\ No newline at end of file
+    pub(crate) fn new(info: &LogInfo, options: &CredentialOptions) -> Self {Self {info: info.clone(), options: options.clone()}}
+
+    /// Flags passwords shared by several sites of the log. Members of a
+    /// cluster share an id derived from the log, the order in which the
+    /// password was first seen and the sites involved, never from the
+    /// password itself.
+    fn mark_reuse(&self, credentials: &mut [Credential]) {
+        let mut sites: HashMap<String, (usize, BTreeSet<String>)> = HashMap::new();
+
+        credentials.iter().for_each(|credential| {
+            if let (Some(password), Some(site)) = (&credential.password, credential.domain().or(credential.url.clone())) {
+                let order = sites.len();
+                sites.entry(password.clone()).or_insert_with(|| (order, BTreeSet::new())).1.insert(site);
+            }
+        });
+
+        let log_key = self.info.hwid().or(self.info.ip()).unwrap_or_default();
+
+        credentials.iter_mut().for_each(|credential| {
+            let (Some(password), Some(metadata)) = (&credential.password, credential.password_metadata.as_mut()) else {
+                return;
+            };
+            let Some((order, sites)) = sites.get(password) else {
+                return;
+            };
+
+            metadata.reuse_count = sites.len();
+            metadata.reused = sites.len() > 1;
+
+            if metadata.reused {
+                let key = format!("{}:{}:{}", log_key, order, sites.iter().cloned().collect::<Vec<_>>().join(","));
+                metadata.reuse_cluster = Some(format!("{:x}", md5::compute(key)));
+            }
+        });
+    }
+
+    fn flush(&self, block: &mut CredentialBlock, credentials: &mut Vec<Credential>) {
+        if block.is_empty() {
+            return;
+        }
+
+        if let Some(credential) = std::mem::take(block).into_credential(&self.info, &self.options) {
+            credentials.push(credential)
+        }
+    }
+}
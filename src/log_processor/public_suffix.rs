@@ -0,0 +1,87 @@
+use std::collections::HashSet;
+
+use lazy_static::lazy_static;
+
+/// Snapshot of https://publicsuffix.org/list/public_suffix_list.dat, ICANN
+/// and private sections.
+const PUBLIC_SUFFIX_LIST: &str = include_str!("public_suffix_list.dat");
+
+struct SuffixRules {
+    rules: HashSet<String>,
+    wildcards: HashSet<String>,
+    exceptions: HashSet<String>,
+}
+
+lazy_static! {
+    static ref SUFFIX_RULES: SuffixRules = {
+        let mut suffixes = SuffixRules {
+            rules: HashSet::new(),
+            wildcards: HashSet::new(),
+            exceptions: HashSet::new(),
+        };
+
+        PUBLIC_SUFFIX_LIST
+            .lines()
+            .map(|line| line.split_whitespace().next().unwrap_or_default())
+            .filter(|rule| !rule.is_empty() && !rule.starts_with("//"))
+            .for_each(|rule| {
+                // Rules are listed in Unicode, hosts are compared in punycode.
+                let ascii = |value: &str| idna::domain_to_ascii(value).unwrap_or(value.to_string());
+
+                if let Some(exception) = rule.strip_prefix('!') {
+                    suffixes.exceptions.insert(ascii(exception));
+                } else if let Some(parent) = rule.strip_prefix("*.") {
+                    suffixes.wildcards.insert(ascii(parent));
+                } else {
+                    suffixes.rules.insert(ascii(rule));
+                }
+            });
+
+        suffixes
+    };
+}
+
+/// Number of trailing labels of `labels` forming its public suffix, applying
+/// the longest matching rule and `*` when nothing matches.
+fn suffix_length(labels: &[&str]) -> usize {
+    let rules = &*SUFFIX_RULES;
+
+    for start in 0..labels.len() {
+        let candidate = labels[start..].join(".");
+
+        if rules.exceptions.contains(&candidate) {
+            return labels.len() - start - 1;
+        }
+        if rules.rules.contains(&candidate) {
+            return labels.len() - start;
+        }
+        if start + 1 < labels.len() && rules.wildcards.contains(&labels[start + 1..].join(".")) {
+            return labels.len() - start;
+        }
+    }
+
+    1
+}
+
+/// Splits an ASCII lowercase host into its registrable domain and the
+/// subdomain in front of it. Hosts that are themselves a public suffix have
+/// no registrable domain.
+pub fn split_host(host: &str) -> (Option<String>, Option<String>) {
+    let labels = host.trim_end_matches('.').split('.').collect::<Vec<_>>();
+
+    if labels.iter().any(|label| label.is_empty()) {
+        return (None, None);
+    }
+
+    let suffix = suffix_length(&labels);
+
+    if suffix >= labels.len() {
+        return (None, None);
+    }
+
+    let registrable_start = labels.len() - suffix - 1;
+    let domain = labels[registrable_start..].join(".");
+    let subdomain = Some(labels[..registrable_start].join(".")).filter(|subdomain| !subdomain.is_empty());
+
+    (Some(domain), subdomain)
+}
@@ -0,0 +1,221 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::AuthCookieService;
+
+use super::{cook_log_processor::CookieDocument, info_log_processor::LogInfo};
+
+// Validity of a cookie is unknown when the log has no infection date; such
+// cookies count for half.
+const UNKNOWN_WEIGHT: f64 = 0.5;
+
+#[derive(Debug, Clone)]
+struct CatalogueEntry {
+    service: String,
+    domains: Vec<String>,
+    cookies: Vec<String>,
+}
+
+impl CatalogueEntry {
+    fn matches_domain(&self, host: &str) -> Option<&str> {
+        let host = host.to_lowercase();
+
+        self.domains
+            .iter()
+            .find(|domain| host == **domain || host.ends_with(&format!(".{}", domain)))
+            .map(|domain| domain.as_str())
+    }
+
+    fn matches_name(&self, name: &str) -> Option<&str> {
+        self.cookies
+            .iter()
+            .find(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => name.starts_with(prefix),
+                None => name == pattern.as_str(),
+            })
+            .map(|pattern| pattern.as_str())
+    }
+}
+
+/// Exposure of one monitored service in a log. Only cookie names are kept;
+/// the catalogue patterns they matched are used for scoring and not indexed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceExposure {
+    service: String,
+    domain: String,
+    cookies: BTreeSet<String>,
+    valid_cookies: BTreeSet<String>,
+    unknown_cookies: BTreeSet<String>,
+    #[serde(skip)]
+    valid_patterns: BTreeSet<String>,
+    #[serde(skip)]
+    unknown_patterns: BTreeSet<String>,
+    expected_cookies: usize,
+    score: u8,
+}
+
+impl ServiceExposure {
+    /// Share of the service's auth cookies the victim had a live session
+    /// cookie for, from 0 to 100.
+    fn compute_score(&mut self) {
+        let unknown = self.unknown_patterns.difference(&self.valid_patterns).count() as f64;
+        let valid = self.valid_patterns.len() as f64;
+        let ratio = (valid + unknown * UNKNOWN_WEIGHT) / self.expected_cookies.max(1) as f64;
+
+        self.score = (ratio.min(1.0) * 100.0).round() as u8;
+    }
+
+    fn merge(&mut self, other: ServiceExposure) {
+        self.cookies.extend(other.cookies);
+        self.valid_cookies.extend(other.valid_cookies);
+        self.unknown_cookies.extend(other.unknown_cookies);
+        self.valid_patterns.extend(other.valid_patterns);
+        self.unknown_patterns.extend(other.unknown_patterns);
+        self.compute_score();
+    }
+}
+
+/// Per-log session exposure, indexed so alerting can sort victims by
+/// `score`, the highest score of their exposed services.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionExposure {
+    archive: String,
+    log_folder: String,
+    hwid: Option<String>,
+    country: Option<String>,
+    infection_date: Option<DateTime<Utc>>,
+    score: u8,
+    exposed_services: usize,
+    services: Vec<ServiceExposure>,
+}
+
+/// Known authentication cookies of the monitored services.
+#[derive(Debug, Clone, Default)]
+pub struct SessionCatalogue {
+    entries: Vec<CatalogueEntry>,
+}
+
+impl SessionCatalogue {
+    pub fn new(services: &[AuthCookieService]) -> Self {
+        let entries = services
+            .iter()
+            .filter(|service| !service.domains.is_empty() && !service.cookies.is_empty())
+            .map(|service| CatalogueEntry {
+                service: service.service.clone(),
+                domains: service
+                    .domains
+                    .iter()
+                    .map(|domain| domain.trim().trim_start_matches('.').to_lowercase())
+                    .collect(),
+                cookies: service.cookies.clone(),
+            })
+            .collect();
+
+        Self { entries }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn score<'a, D: IntoIterator<Item = &'a CookieDocument>>(&self, documents: D) -> Vec<ServiceExposure> {
+        let mut exposures: BTreeMap<(String, String), ServiceExposure> = BTreeMap::new();
+
+        documents
+            .into_iter()
+            .flat_map(|document| document.cookies())
+            .for_each(|cookie| {
+                self.entries.iter().for_each(|entry| {
+                    let (Some(domain), Some(pattern)) = (entry.matches_domain(cookie.host()), entry.matches_name(cookie.name())) else {
+                        return;
+                    };
+
+                    let exposure = exposures
+                        .entry((entry.service.clone(), domain.to_string()))
+                        .or_insert(ServiceExposure {
+                            service: entry.service.clone(),
+                            domain: domain.to_string(),
+                            cookies: BTreeSet::new(),
+                            valid_cookies: BTreeSet::new(),
+                            unknown_cookies: BTreeSet::new(),
+                            valid_patterns: BTreeSet::new(),
+                            unknown_patterns: BTreeSet::new(),
+                            expected_cookies: entry.cookies.len(),
+                            score: 0,
+                        });
+
+                    exposure.cookies.insert(cookie.name().to_string());
+                    match cookie.valid_at_infection() {
+                        Some(true) => {
+                            exposure.valid_cookies.insert(cookie.name().to_string());
+                            exposure.valid_patterns.insert(pattern.to_string());
+                        }
+                        Some(false) => {}
+                        None => {
+                            exposure.unknown_cookies.insert(cookie.name().to_string());
+                            exposure.unknown_patterns.insert(pattern.to_string());
+                        }
+                    }
+                });
+            });
+
+        exposures
+            .into_values()
+            .map(|mut exposure| {
+                exposure.compute_score();
+                exposure
+            })
+            .collect()
+    }
+}
+
+/// Merges the service exposures of every cookie file of a log.
+#[derive(Debug, Default)]
+pub struct SessionCollector {
+    logs: HashMap<String, SessionExposure>,
+}
+
+impl SessionCollector {
+    pub fn add<A: AsRef<str>, L: AsRef<str>>(&mut self, archive: A, log_folder: L, info: &LogInfo, services: Vec<ServiceExposure>) {
+        if services.is_empty() {
+            return;
+        }
+
+        let log = self
+            .logs
+            .entry(log_folder.as_ref().to_string())
+            .or_insert(SessionExposure {
+                archive: archive.as_ref().to_string(),
+                log_folder: log_folder.as_ref().to_string(),
+                hwid: info.hwid(),
+                country: info.country(),
+                infection_date: info.infection_date(),
+                score: 0,
+                exposed_services: 0,
+                services: Vec::new(),
+            });
+
+        services.into_iter().for_each(|service| {
+            match log
+                .services
+                .iter_mut()
+                .find(|known| known.service == service.service && known.domain == service.domain)
+            {
+                Some(known) => known.merge(service),
+                None => log.services.push(service),
+            }
+        });
+
+        log.score = log.services.iter().map(|service| service.score).max().unwrap_or(0);
+        log.exposed_services = log.services.iter().filter(|service| service.score > 0).count();
+    }
+
+    pub fn documents(&mut self) -> Vec<SessionExposure> {
+        let mut documents = self.logs.drain().map(|(_, log)| log).collect::<Vec<_>>();
+
+        documents.sort_by(|a, b| b.score.cmp(&a.score).then(a.log_folder.cmp(&b.log_folder)));
+        documents
+    }
+}
@@ -0,0 +1,187 @@
+use std::collections::BTreeMap;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    info_log_processor::{LogInfo, LIST_MARKER_REGEX},
+    is_separator, LogProcessor,
+};
+
+lazy_static! {
+    // `Google Chrome [119.0.6045.160]`, `7-Zip (23.01)`
+    static ref REGEX_BRACKET_VERSION: Regex = Regex::new(r"^(.*?)\s*[\[(]v?(\d[\w.\-]*)[\])]$").unwrap();
+    // `Notepad++ - 8.5.8`
+    static ref REGEX_DASH_VERSION: Regex = Regex::new(r"^(.*?)\s+-\s+v?(\d[\w.\-]*)$").unwrap();
+    static ref REGEX_PARENTHESES: Regex = Regex::new(r"\s*[\[(][^\])]*[\])]").unwrap();
+    static ref REGEX_VERSION_TOKEN: Regex = Regex::new(r"(?i)\s+(?:v(?:ersion)?\s*)?\d+(?:\.\d+)+\S*").unwrap();
+    static ref REGEX_ARCH_TOKEN: Regex = Regex::new(r"(?i)\s+(?:x64|x86|64-bit|32-bit|amd64)\b").unwrap();
+}
+
+const NAME_KEYS: &[&str] = &["name", "program", "application", "software", "displayname", "display name"];
+const VERSION_KEYS: &[&str] = &["version", "displayversion", "display version"];
+const PUBLISHER_KEYS: &[&str] = &["publisher", "vendor", "company", "manufacturer"];
+
+/// Lowercased product name without version, architecture or bracketed
+/// details, so `Mozilla Firefox (x64 en-US)` and `Mozilla Firefox 120.0`
+/// aggregate together.
+pub fn normalize_product(name: &str) -> String {
+    let name = REGEX_PARENTHESES.replace_all(name, "");
+    let name = REGEX_VERSION_TOKEN.replace_all(&name, "");
+    let name = REGEX_ARCH_TOKEN.replace_all(&name, "");
+
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_end_matches([' ', '-'])
+        .to_lowercase()
+}
+
+#[derive(Default)]
+struct SoftwareEntry {
+    name: Option<String>,
+    version: Option<String>,
+    publisher: Option<String>,
+}
+
+impl SoftwareEntry {
+    fn from_line(line: &str) -> Self {
+        let columns: Vec<_> = line.split(['\t', '|']).map(|column| column.trim()).collect();
+
+        if columns.len() > 1 {
+            return Self {
+                name: columns.first().map(|name| name.to_string()),
+                version: columns.get(1).filter(|version| !version.is_empty()).map(|version| version.to_string()),
+                publisher: columns.get(2).filter(|publisher| !publisher.is_empty()).map(|publisher| publisher.to_string()),
+            };
+        }
+
+        match REGEX_BRACKET_VERSION
+            .captures(line)
+            .or_else(|| REGEX_DASH_VERSION.captures(line))
+            .filter(|captures| !captures[1].trim().is_empty())
+        {
+            Some(captures) => Self {
+                name: Some(captures[1].trim().to_string()),
+                version: Some(captures[2].to_string()),
+                publisher: None,
+            },
+            None => Self { name: Some(line.to_string()), ..Self::default() },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoftwareDocument {
+    archive: String,
+    log_folder: String,
+    hwid: Option<String>,
+    country: Option<String>,
+    family: Option<String>,
+    name: String,
+    normalized_name: String,
+    version: Option<String>,
+    publisher: Option<String>,
+}
+
+pub struct SoftwareLogProcessor {
+    info: LogInfo,
+    archive: String,
+    log_folder: String,
+}
+
+impl LogProcessor for SoftwareLogProcessor {
+    type Out = Vec<SoftwareDocument>;
+
+    /// Reads one program per line (`Name [version]`, `Name - version`,
+    /// tab or `|` separated `name, version, publisher` columns) or
+    /// `Name:` / `Version:` / `Publisher:` blocks. Programs listed in the
+    /// software section of the info file are added too.
+    fn parse<C: AsRef<str>>(&self, content: C) -> Self::Out {
+        let mut programs: BTreeMap<(String, Option<String>), SoftwareDocument> = BTreeMap::new();
+        let mut block = SoftwareEntry::default();
+
+        let mut add = |entry: SoftwareEntry| {
+            let Some(name) = entry.name.filter(|name| !name.is_empty()) else {
+                return;
+            };
+            let normalized_name = normalize_product(&name);
+
+            if normalized_name.is_empty() {
+                return;
+            }
+
+            programs
+                .entry((normalized_name.clone(), entry.version.clone()))
+                .or_insert(SoftwareDocument {
+                    archive: self.archive.clone(),
+                    log_folder: self.log_folder.clone(),
+                    hwid: self.info.hwid(),
+                    country: self.info.country(),
+                    family: self.info.family(),
+                    name,
+                    normalized_name,
+                    version: entry.version,
+                    publisher: entry.publisher,
+                });
+        };
+
+        let lines = content
+            .as_ref()
+            .lines()
+            .map(|line| line.to_string())
+            .chain(self.info.installed_software().iter().cloned())
+            .collect::<Vec<_>>();
+
+        for line in lines {
+            let trimmed = line.trim();
+            let line = LIST_MARKER_REGEX.replace(trimmed, "").trim().to_string();
+
+            if line.is_empty() || is_separator(trimmed) {
+                add(std::mem::take(&mut block));
+                continue;
+            }
+
+            if let Some((key, value)) = line.split_once(':') {
+                let key = key.trim().to_lowercase();
+                let value = Some(value.trim().to_string()).filter(|value| !value.is_empty());
+
+                if NAME_KEYS.contains(&key.as_str()) {
+                    add(std::mem::take(&mut block));
+                    block.name = value;
+                    continue;
+                }
+                if VERSION_KEYS.contains(&key.as_str()) {
+                    block.version = value;
+                    continue;
+                }
+                if PUBLISHER_KEYS.contains(&key.as_str()) {
+                    block.publisher = value;
+                    continue;
+                }
+                // Section headers such as `Installed Software:`
+                if value.is_none() {
+                    continue;
+                }
+            }
+
+            add(std::mem::take(&mut block));
+            add(SoftwareEntry::from_line(&line));
+        }
+
+        add(block);
+
+        programs.into_values().collect()
+    }
+}
+
+impl SoftwareLogProcessor {
+    pub fn new<A: AsRef<str>, L: AsRef<str>>(info: &LogInfo, archive: A, log_folder: L) -> Self {
+        Self {
+            info: info.clone(),
+            archive: archive.as_ref().to_string(),
+            log_folder: log_folder.as_ref().to_string(),
+        }
+    }
+}
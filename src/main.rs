@@ -1,27 +1,63 @@
 mod archive;
+mod config;
+mod directory;
 mod elastic_client;
+mod enrichment;
+mod export;
+mod exposure;
 mod log_processor;
+mod privacy;
 
-use std::sync::Arc;
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
 
 use lazy_static::lazy_static;
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use regex::Regex;
+use serde::Serialize;
 use serde_json::{json, Value};
+use tokio::{
+    sync::mpsc::{Receiver, Sender},
+    task::JoinHandle,
+};
 
 use crate::{
     archive::{z_archive::Zarchive, Archive, ArchiveFilter, ArchiveUtils, SupportedExtension},
+    config::{CookieMode, Settings},
+    directory::{DirectoryChecker, DirectoryReport},
     elastic_client::{ElasticIndexMapping, ElasticsearchClient},
+    enrichment::geoip::GeoIpEnricher,
+    export::{misp::MispExporter, stix::StixExporter, ArchiveIntel, ExportUtils},
+    exposure::{
+        format_range,
+        server::{ExposureServer, DEFAULT_LISTEN},
+        to_documents, ExposureHasher, ExposureKind, ExposureLookup,
+    },
     log_processor::{
-        cook_log_processor::CookieLogProcessor, info_log_processor::InfoLogProcessor,
-        log_filter::LogFilter, pass_log_processor::PassLogProcessor, LogProcessor,
+        autofill_log_processor::AutofillLogProcessor,
+        cook_log_processor::{CookieLogProcessor, CookieOptions, CookieSource},
+        history_log_processor::HistoryLogProcessor,
+        info_log_processor::{detect_family, HostDocument, InfoLogProcessor, LogInfoFields},
+        ioc_log_processor::{IocCollector, IocLogProcessor},
+        log_filter::LogFilter,
+        pass_log_processor::{CredentialOptions, PassLogProcessor},
+        session_exposure::{SessionCatalogue, SessionCollector},
+        software_log_processor::SoftwareLogProcessor,
+        grabbed_files::{grabber_filter, is_grabbed, GrabbedFileDocument},
+        LogProcessor,
     },
+    privacy::PrivacyPolicy,
 };
 
 lazy_static! {
     static ref SYSTEM_INFO_REGEX: Regex = Regex::new(r"(?i)((system)|(info))").unwrap();
     static ref PASSWORD_REGEX: Regex = Regex::new(r"(?i)(pass)").unwrap();
     static ref COOKIES_REGEX: Regex = Regex::new(r"(?i)(cookies)").unwrap();
+    static ref HISTORY_REGEX: Regex = Regex::new(r"(?i)(history)").unwrap();
+    static ref AUTOFILL_REGEX: Regex = Regex::new(r"(?i)(autofill)").unwrap();
+    static ref SOFTWARE_REGEX: Regex = Regex::new(r"(?i)((software)|(programs))").unwrap();
 }
 
 #[tokio::main]
@@ -37,6 +73,35 @@ async fn main() -> tokio::io::Result<()> {
               "domain": {
                 "type": "keyword"
               },
+              "privacy_policy": {
+                "type": "keyword"
+              },
+              "hwid": {
+                "type": "keyword"
+              },
+              "archive": {
+                "type": "keyword"
+              },
+              "log_folder": {
+                "type": "keyword"
+              },
+              "source_path": {
+                "type": "keyword"
+              },
+              "browser": {
+                "type": "keyword"
+              },
+              "profile": {
+                "type": "keyword"
+              },
+              "geo": {
+                "properties": {
+                  "country_code": {"type": "keyword"},
+                  "city": {"type": "keyword"},
+                  "asn": {"type": "long"},
+                  "organization": {"type": "keyword"}
+                }
+              },
               "cookies": {
                 "type": "nested",
                 "properties": {
@@ -45,6 +110,33 @@ async fn main() -> tokio::io::Result<()> {
                   },
                   "value": {
                     "type": "text"
+                  },
+                  "value_hash": {
+                    "type": "keyword"
+                  },
+                  "domain": {
+                    "type": "keyword"
+                  },
+                  "include_subdomains": {
+                    "type": "boolean"
+                  },
+                  "http_only": {
+                    "type": "boolean"
+                  },
+                  "path": {
+                    "type": "keyword"
+                  },
+                  "secure": {
+                    "type": "boolean"
+                  },
+                  "expires_at": {
+                    "type": "date"
+                  },
+                  "session": {
+                    "type": "boolean"
+                  },
+                  "valid_at_infection": {
+                    "type": "boolean"
                   }
                 }
               }
@@ -59,16 +151,264 @@ async fn main() -> tokio::io::Result<()> {
                 "properties": {
                     "url": {"type": "keyword"},
                     "username": {"type": "keyword"},
-                    "password": {"type": "keyword"}
+                    "password": {"type": "keyword"},
+                    "application": {"type": "keyword"},
+                    "profile": {"type": "keyword"},
+                    "origin": {"type": "keyword"},
+                    "app_owner_domain": {"type": "keyword"},
+                    "privacy_policy": {"type": "keyword"},
+                    "password_metadata": {
+                        "properties": {
+                            "length": {"type": "integer"},
+                            "lowercase": {"type": "boolean"},
+                            "uppercase": {"type": "boolean"},
+                            "digits": {"type": "boolean"},
+                            "symbols": {"type": "boolean"},
+                            "class_count": {"type": "integer"},
+                            "equals_username": {"type": "boolean"},
+                            "reuse_count": {"type": "integer"},
+                            "reused": {"type": "boolean"},
+                            "reuse_cluster": {"type": "keyword"}
+                        }
+                    },
+                    "identity": {
+                        "properties": {
+                            "kind": {"type": "keyword"},
+                            "normalized": {"type": "keyword"},
+                            "email_domain": {"type": "keyword"}
+                        }
+                    },
+                    "url_parts": {
+                        "properties": {
+                            "scheme": {"type": "keyword"},
+                            "host": {"type": "keyword"},
+                            "port": {"type": "integer"},
+                            "path": {"type": "keyword"},
+                            "domain": {"type": "keyword"},
+                            "subdomain": {"type": "keyword"},
+                            "package": {"type": "keyword"}
+                        }
+                    },
+                    "infos": {
+                        "properties": {
+                            "country": {"type": "keyword"},
+                            "country_original": {"type": "keyword"},
+                            "hwid": {"type": "keyword"},
+                            "ip": {"type": "ip", "ignore_malformed": true},
+                            "build_id": {"type": "keyword"},
+                            "malware_path": {"type": "keyword"},
+                            "dropper": {"type": "keyword"},
+                            "family": {"type": "keyword"},
+                            "infection_date": {"type": "date"},
+                            "geo": {
+                                "properties": {
+                                    "country_code": {"type": "keyword"},
+                                    "city": {"type": "keyword"},
+                                    "asn": {"type": "long"},
+                                    "organization": {"type": "keyword"}
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }),
+    );
+    let elastic_iocs_mapping = ElasticIndexMapping::new(
+        "iocs",
+        json!({
+            "mappings": {
+                "properties": {
+                    "kind": {"type": "keyword"},
+                    "value": {"type": "keyword"},
+                    "archive": {"type": "keyword"},
+                    "log_count": {"type": "integer"},
+                    "logs": {"type": "keyword"}
+                }
+            }
+        }),
+    );
+    let elastic_sessions_mapping = ElasticIndexMapping::new(
+        "sessions",
+        json!({
+            "mappings": {
+                "properties": {
+                    "archive": {"type": "keyword"},
+                    "log_folder": {"type": "keyword"},
+                    "hwid": {"type": "keyword"},
+                    "country": {"type": "keyword"},
+                    "infection_date": {"type": "date"},
+                    "score": {"type": "integer"},
+                    "exposed_services": {"type": "integer"},
+                    "services": {
+                        "type": "nested",
+                        "properties": {
+                            "service": {"type": "keyword"},
+                            "domain": {"type": "keyword"},
+                            "cookies": {"type": "keyword"},
+                            "valid_cookies": {"type": "keyword"},
+                            "unknown_cookies": {"type": "keyword"},
+                            "expected_cookies": {"type": "integer"},
+                            "score": {"type": "integer"}
+                        }
+                    }
+                }
+            }
+        }),
+    );
+    let elastic_history_mapping = ElasticIndexMapping::new(
+        "history",
+        json!({
+            "mappings": {
+                "properties": {
+                    "archive": {"type": "keyword"},
+                    "log_folder": {"type": "keyword"},
+                    "hwid": {"type": "keyword"},
+                    "country": {"type": "keyword"},
+                    "domain": {"type": "keyword"},
+                    "visit_count": {"type": "long"},
+                    "last_visit": {"type": "date"},
+                    "privacy_policy": {"type": "keyword"}
+                }
+            }
+        }),
+    );
+    let elastic_autofill_mapping = ElasticIndexMapping::new(
+        "autofill",
+        json!({
+            "mappings": {
+                "properties": {
+                    "archive": {"type": "keyword"},
+                    "log_folder": {"type": "keyword"},
+                    "hwid": {"type": "keyword"},
+                    "country": {"type": "keyword"},
+                    "email_domains": {"type": "keyword"},
+                    "privacy_policy": {"type": "keyword"},
+                    "entries": {
+                        "type": "nested",
+                        "properties": {
+                            "field": {"type": "keyword"},
+                            "kind": {"type": "keyword"},
+                            "value": {"type": "keyword"},
+                            "email_domain": {"type": "keyword"}
+                        }
+                    }
+                }
+            }
+        }),
+    );
+    let elastic_software_mapping = ElasticIndexMapping::new(
+        "software",
+        json!({
+            "mappings": {
+                "properties": {
+                    "archive": {"type": "keyword"},
+                    "log_folder": {"type": "keyword"},
+                    "hwid": {"type": "keyword"},
+                    "country": {"type": "keyword"},
+                    "family": {"type": "keyword"},
+                    "name": {"type": "keyword"},
+                    "normalized_name": {"type": "keyword"},
+                    "version": {"type": "keyword"},
+                    "publisher": {"type": "keyword"},
+                    "privacy_policy": {"type": "keyword"}
+                }
+            }
+        }),
+    );
+    let elastic_hosts_mapping = ElasticIndexMapping::new(
+        "hosts",
+        json!({
+            "mappings": {
+                "properties": {
+                    "archive": {"type": "keyword"},
+                    "log_folder": {"type": "keyword"},
+                    "hwid": {"type": "keyword"},
+                    "country": {"type": "keyword"},
+                    "family": {"type": "keyword"},
+                    "infection_date": {"type": "date"},
+                    "processes": {"type": "keyword"},
+                    "hardware": {"type": "keyword"},
+                    "privacy_policy": {"type": "keyword"}
+                }
+            }
+        }),
+    );
+    let elastic_grabbed_files_mapping = ElasticIndexMapping::new(
+        "grabbed_files",
+        json!({
+            "mappings": {
+                "properties": {
+                    "archive": {"type": "keyword"},
+                    "log_folder": {"type": "keyword"},
+                    "hwid": {"type": "keyword"},
+                    "path": {"type": "keyword"},
+                    "file_name": {"type": "keyword"},
+                    "extension": {"type": "keyword"},
+                    "size": {"type": "long"},
+                    "sha256": {"type": "keyword"},
+                    "category": {"type": "keyword"},
+                    "privacy_policy": {"type": "keyword"}
+                }
+            }
+        }),
+    );
+    let elastic_exposures_mapping = ElasticIndexMapping::new(
+        "exposures",
+        json!({
+            "mappings": {
+                "properties": {
+                    "kind": {"type": "keyword"},
+                    "prefix": {"type": "keyword"},
+                    "suffix": {"type": "keyword"}
                 }
             }
         }),
     );
 
+    let settings = Settings::load()?;
+    let geoip = GeoIpEnricher::new(&settings.geoip)?;
+    let credential_options = Arc::new(CredentialOptions::new(&settings.credentials));
+    let privacy = Arc::new(PrivacyPolicy::new(&settings.privacy)?);
+    println!("[+] Privacy policy {}", privacy.name());
+    let directory = DirectoryChecker::new(&settings.directory)?.map(Arc::new);
+    if let Some(directory) = &directory {
+        println!("[+] Directory export with {} accounts", directory.len());
+    }
+
+    let exposure_hasher = ExposureHasher::new(&settings.exposure, &settings.privacy)?.map(Arc::new);
+    if exposure_hasher.is_none() {
+        println!("[+] Exposure records are disabled, no exposure key configured");
+    }
+
     let elastic = Arc::new(ElasticsearchClient::new().await?);
 
+    let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+    let cookie_mode = match args.iter().position(|arg| arg == "--cookie-metadata") {
+        Some(index) => {
+            args.remove(index);
+            CookieMode::Metadata
+        }
+        None => settings.cookies.mode,
+    };
+    let cookie_options = CookieOptions::new(cookie_mode, settings.privacy.key.as_deref())?;
+    if cookie_options.is_metadata_only() {
+        println!("[+] Cookie values are not indexed");
+    }
+    let session_catalogue = Arc::new(SessionCatalogue::new(&settings.cookies.auth_cookies));
+
+    if !args.is_empty() {
+        return run_command(&args, &settings, elastic.clone(), exposure_hasher.clone()).await;
+    }
+
     let (tx_cookies, mut rx_cookies) = tokio::sync::mpsc::channel::<Vec<Value>>(4096);
     let (tx_passwd, mut rx_passwd) = tokio::sync::mpsc::channel::<Vec<Value>>(4096);
+    let (tx_exposures, mut rx_exposures) = tokio::sync::mpsc::channel::<Vec<Value>>(4096);
+    let (tx_history, mut rx_history) = tokio::sync::mpsc::channel::<Vec<Value>>(4096);
+    let (tx_autofill, mut rx_autofill) = tokio::sync::mpsc::channel::<Vec<Value>>(4096);
+    let (tx_software, mut rx_software) = tokio::sync::mpsc::channel::<Vec<Value>>(4096);
+    let (tx_grabbed, mut rx_grabbed) = tokio::sync::mpsc::channel::<Vec<Value>>(4096);
+    let (tx_hosts, mut rx_hosts) = tokio::sync::mpsc::channel::<Vec<Value>>(4096);
     let time = std::time::Instant::now();
 
     let select_file = rfd::AsyncFileDialog::new()
@@ -102,30 +442,70 @@ async fn main() -> tokio::io::Result<()> {
     
     let mut filter = LogFilter::new(
         Some(vec![
-            Regex::new(r"(?i)((pass)|(system)|(info)|(cookies))").unwrap()
+            Regex::new(r"(?i)((pass)|(system)|(info)|(cookies)|(history)|(autofill)|(software)|(programs))").unwrap()
         ]),
-        Some(vec![String::from("txt")]),
+        Some(vec![String::from("txt"), String::from("json")]),
     );
 
-    let content = archive.enumerate(filter.clone());
+    // Grabbed files are only inventoried, their content is never parsed.
+    let content = archive
+        .enumerate(filter.clone())
+        .into_iter()
+        .filter(|item| !is_grabbed(item))
+        .collect::<Vec<_>>();
     let mut logs = filter.relation_mapper(content).to_owned();
+    let grabbed = archive
+        .enumerate(grabber_filter())
+        .into_iter()
+        .filter(|item| !item.ends_with('/'))
+        .collect::<Vec<_>>();
+    let grabbed = grabber_filter().relation_mapper(grabbed).to_owned();
     let mut tasks = Vec::new();
+    let mut iocs = IocCollector::new(&filehash);
+    let mut intel = ArchiveIntel::new(&filehash);
+    let exposed_domains = Arc::new(Mutex::new(BTreeMap::<String, usize>::new()));
+    let directory_matches = Arc::new(Mutex::new(DirectoryReport::default()));
+    let sessions = Arc::new(Mutex::new(SessionCollector::default()));
+    let archive_family = filename
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(detect_family);
 
     let c_elastic = elastic.clone();
 
     let _ = c_elastic.create_indice(elastic_cookies_mapping).await;
     let _ = c_elastic.create_indice(elastic_credentials_mapping).await;
+    let _ = c_elastic.create_indice(elastic_iocs_mapping).await;
+    let _ = c_elastic.create_indice(elastic_exposures_mapping).await;
+    let _ = c_elastic.create_indice(elastic_sessions_mapping).await;
+    let _ = c_elastic.create_indice(elastic_history_mapping).await;
+    let _ = c_elastic.create_indice(elastic_autofill_mapping).await;
+    let _ = c_elastic.create_indice(elastic_software_mapping).await;
+    let _ = c_elastic.create_indice(elastic_grabbed_files_mapping).await;
+    let _ = c_elastic.create_indice(elastic_hosts_mapping).await;
 
-    logs.drain().for_each(|(_, filenames)| {
+    logs.drain().for_each(|(log_folder, filenames)| {
         let mut filenames_into_iter = filenames.iter();
 
         let get_infos_filename =
-            filenames_into_iter.find(|filename| SYSTEM_INFO_REGEX.is_match(&filename));
+            filenames_into_iter.find(|filename| SYSTEM_INFO_REGEX.is_match(&filename) && filename.ends_with("txt"));
         let get_passw_filename =
-            filenames_into_iter.find(|filename| PASSWORD_REGEX.is_match(&filename));
+            filenames_into_iter.find(|filename| PASSWORD_REGEX.is_match(&filename) && filename.ends_with("txt"));
         let get_cooks_filename: Vec<_> = filenames_into_iter
             .filter(|filename| COOKIES_REGEX.is_match(&filename))
             .collect();
+        let get_history_filename: Vec<_> = filenames
+            .iter()
+            .filter(|filename| HISTORY_REGEX.is_match(filename) && filename.ends_with("txt"))
+            .collect();
+        let get_autofill_filename: Vec<_> = filenames
+            .iter()
+            .filter(|filename| AUTOFILL_REGEX.is_match(filename))
+            .collect();
+        let get_software_filename: Vec<_> = filenames
+            .iter()
+            .filter(|filename| SOFTWARE_REGEX.is_match(filename))
+            .collect();
 
         if let (Some(infos_filename), Some(passw_filename)) =
             (get_infos_filename, get_passw_filename)
@@ -140,16 +520,78 @@ async fn main() -> tokio::io::Result<()> {
             };
 
             let info_processor = InfoLogProcessor::new();
-            let info = Arc::new(info_processor.parse(content));
+            let mut info = geoip.enrich(info_processor.parse(&content));
+
+            // Archives are usually named after the family they were sold as.
+            if let (None, Some(family)) = (info.family(), archive_family) {
+                info.set(LogInfoFields::Family(family.to_string()));
+            }
+
+            intel.add(info.clone());
+            let info = Arc::new(info);
+
+            let ioc_processor = IocLogProcessor::new(&info);
+            iocs.add(&log_folder, ioc_processor.parse(&content));
+
+            if let Some(document) = HostDocument::new(&filehash, &log_folder, &info).and_then(|item| serde_json::to_value(item).ok()) {
+                let sender = tx_hosts.clone();
+
+                let host_task = tokio::spawn(async move {
+                    let _ = sender.send(vec![document]).await;
+                });
+
+                tasks.push(host_task)
+            }
 
             if let Ok(content) = archive.reader(passw_filename) {
                 let info = info.clone();
 
                 let sender = tx_passwd.clone();
+                let exposure_sender = tx_exposures.clone();
+                let exposed_domains = exposed_domains.clone();
+                let directory = directory.clone();
+                let directory_matches = directory_matches.clone();
+                let options = credential_options.clone();
+                let hasher = exposure_hasher.clone();
                 let passw_task = tokio::spawn(async move {
-                    let passw_processor = PassLogProcessor::new(&info);
-                    let passw_parser = passw_processor
-                        .parse(&content)
+                    let passw_processor = PassLogProcessor::new(&info, &options);
+                    let credentials = passw_processor.parse(&content);
+
+                    {
+                        let mut domains = exposed_domains.lock().unwrap();
+                        credentials
+                            .iter()
+                            .filter_map(|credential| credential.domain())
+                            .for_each(|host| *domains.entry(host).or_default() += 1);
+                    }
+
+                    // Runs on the plaintext, before the privacy policy. bcrypt
+                    // checks are slow, so they stay off the runtime workers.
+                    if let Some(directory) = directory {
+                        let (credentials, info) = (credentials.clone(), info.clone());
+                        let checked = tokio::task::spawn_blocking(move || {
+                            credentials
+                                .iter()
+                                .filter_map(|credential| directory.check(credential, &info))
+                                .collect::<Vec<_>>()
+                        })
+                        .await;
+
+                        if let Ok(matches) = checked {
+                            let mut report = directory_matches.lock().unwrap();
+                            matches.into_iter().for_each(|found| report.add(found));
+                        }
+                    }
+
+                    if let Some(hasher) = &hasher {
+                        let exposures = credentials
+                            .iter()
+                            .flat_map(|credential| hasher.records(credential))
+                            .collect::<Vec<_>>();
+                        let _ = exposure_sender.send(to_documents(exposures)).await;
+                    }
+
+                    let passw_parser = credentials
                         .par_iter()
                         .map(|item| item.to_owned())
                         .map(|item| serde_json::to_value(item))
@@ -162,16 +604,70 @@ async fn main() -> tokio::io::Result<()> {
                 tasks.push(passw_task)
             }
 
+            if !get_history_filename.is_empty() {
+                // Every browser of the log is aggregated into one set of
+                // domains.
+                let content = read_joined(&mut archive, &get_history_filename);
+                let processor = HistoryLogProcessor::new(&info, &filehash, &log_folder);
+
+                tasks.push(spawn_documents(processor, content, tx_history.clone()))
+            }
+
+            if !get_software_filename.is_empty() || !info.installed_software().is_empty() {
+                let content = read_joined(&mut archive, &get_software_filename);
+                let processor = SoftwareLogProcessor::new(&info, &filehash, &log_folder);
+
+                tasks.push(spawn_documents(processor, content, tx_software.clone()))
+            }
+
+            if let Some(grabbed_filenames) = grabbed.get(&log_folder) {
+                let documents = grabbed_filenames
+                    .iter()
+                    .filter_map(|item| match archive.digest(item) {
+                        Ok(digest) => Some(GrabbedFileDocument::new(&filehash, &log_folder, item, &info, digest)),
+                        Err(err) => {
+                            eprintln!("[-] Cannot hash grabbed file {} {}", item, err);
+                            None
+                        }
+                    })
+                    .filter_map(|item| serde_json::to_value(item).ok())
+                    .collect::<Vec<_>>();
+                let sender = tx_grabbed.clone();
+
+                let grabbed_task = tokio::spawn(async move {
+                    let _ = sender.send(documents).await;
+                });
+
+                tasks.push(grabbed_task)
+            }
+
+            if !get_autofill_filename.is_empty() {
+                let content = read_joined(&mut archive, &get_autofill_filename);
+                let processor = AutofillLogProcessor::new(&info, &filehash, &log_folder);
+
+                tasks.push(spawn_documents(processor, content, tx_autofill.clone()))
+            }
+
             if !get_cooks_filename.is_empty() {
                 for item in get_cooks_filename {
                     let sender = tx_cookies.clone();
                     let info = info.clone();
+                    let source = CookieSource::new(&filehash, &log_folder, item);
+                    let options = cookie_options.clone();
+                    let catalogue = session_catalogue.clone();
+                    let sessions = sessions.clone();
+                    let (archive_hash, log_folder) = (filehash.clone(), log_folder.clone());
                     if let Ok(content) = archive.reader(&item) {
                         let cookie_task = tokio::spawn(async move {
-                            let cookie_processor = CookieLogProcessor::new(&info);
+                            let cookie_processor = CookieLogProcessor::new(&info, source, &options);
 
                             match cookie_processor.parse(content) {
                                 Ok(parsed) => {
+                                    if !catalogue.is_empty() {
+                                        let services = catalogue.score(parsed.values());
+                                        sessions.lock().unwrap().add(&archive_hash, &log_folder, &info, services);
+                                    }
+
                                     let document = parsed
                                         .values()
                                         .map(|item| item.to_owned())
@@ -197,38 +693,195 @@ async fn main() -> tokio::io::Result<()> {
     if logs.is_empty() {
         rx_cookies.close();
         rx_passwd.close();
+        rx_exposures.close();
+        rx_history.close();
+        rx_autofill.close();
+        rx_software.close();
+        rx_grabbed.close();
+        rx_hosts.close();
         let _ = ArchiveUtils::register_hash(&filehash);
     }
 
-    let c2_elastic = elastic.clone();
+    if !iocs.is_empty() {
+        let documents = iocs.documents();
 
-    let receiver_cookies = tokio::task::spawn(async move {
-        while let Some(data) = rx_cookies.recv().await {
-            c2_elastic.insert_many("cookies", data).await;
+        match ExportUtils::write_json(format!("{}_iocs.json", filehash), &documents) {
+            Ok(path) => println!("[+] IOC export written to {}", path.display()),
+            Err(err) => eprintln!("[-] IOC export error {}", err),
         }
-    }); 
 
-    tasks.push(receiver_cookies);
+        elastic.insert_many("iocs", documents).await;
+    }
 
-    let c3_elastic = elastic.clone();
+    if !intel.is_empty() {
+        let stix = StixExporter::new(&settings.stix);
 
-    let receiver_passwd = tokio::task::spawn(async move {
-        while let Some(data) = rx_passwd.recv().await {
-            c3_elastic.insert_many("credentials", data).await;
+        match ExportUtils::write_json(stix.file_name(&intel), &stix.bundle(&intel)) {
+            Ok(path) => println!("[+] STIX bundle written to {}", path.display()),
+            Err(err) => eprintln!("[-] STIX export error {}", err),
         }
-    });
+    }
 
-    tasks.push(receiver_passwd);
+    tasks.push(spawn_receiver(elastic.clone(), Some(privacy.clone()), "cookies", rx_cookies));
+    tasks.push(spawn_receiver(elastic.clone(), Some(privacy.clone()), "credentials", rx_passwd));
+    // Only hashes are sent here; password records are already skipped
+    // when the privacy policy drops or masks passwords.
+    tasks.push(spawn_receiver(elastic.clone(), None, "exposures", rx_exposures));
+    tasks.push(spawn_receiver(elastic.clone(), Some(privacy.clone()), "history", rx_history));
+    tasks.push(spawn_receiver(elastic.clone(), Some(privacy.clone()), "autofill", rx_autofill));
+    tasks.push(spawn_receiver(elastic.clone(), Some(privacy.clone()), "software", rx_software));
+    tasks.push(spawn_receiver(elastic.clone(), Some(privacy.clone()), "grabbed_files", rx_grabbed));
+    tasks.push(spawn_receiver(elastic.clone(), Some(privacy.clone()), "hosts", rx_hosts));
 
     for task in tasks {
         task.await?
     }
 
+    if !intel.is_empty() {
+        intel.add_domains(std::mem::take(&mut *exposed_domains.lock().unwrap()));
+
+        let misp = MispExporter::new(&settings.misp, &settings.directory.domains);
+
+        match ExportUtils::write_json(misp.file_name(&intel), &misp.event(&intel)) {
+            Ok(path) => println!("[+] MISP event written to {}", path.display()),
+            Err(err) => eprintln!("[-] MISP export error {}", err),
+        }
+    }
+
+    let mut session_documents = sessions
+        .lock()
+        .unwrap()
+        .documents()
+        .iter()
+        .filter_map(|item| serde_json::to_value(item).ok())
+        .collect::<Vec<_>>();
+    if !session_documents.is_empty() {
+        println!("[+] {} logs with monitored sessions", session_documents.len());
+        session_documents.iter_mut().for_each(|document| privacy.apply(document));
+        elastic.insert_many("sessions", session_documents).await;
+    }
+
+    if directory.is_some() {
+        let report = std::mem::take(&mut *directory_matches.lock().unwrap());
+        let matches = report.matches();
+
+        match ExportUtils::write_json(format!("{}_directory_matches.json", filehash), &matches) {
+            Ok(path) => println!("[+] {} directory accounts matched, report written to {}", matches.len(), path.display()),
+            Err(err) => eprintln!("[-] Directory report error {}", err),
+        }
+    }
+
     println!("Elapsed at: {}", time.elapsed().as_millis());
 
     Ok(())
 }
 
+/// Reads and joins every file of a log that feeds the same processor, so
+/// e.g. the history of every browser is aggregated into one set of domains.
+fn read_joined<A: Archive>(archive: &mut A, filenames: &[&String]) -> String {
+    filenames
+        .iter()
+        .filter_map(|item| archive.reader(item).ok())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Parses `content` on its own task and sends the documents to the
+/// receiver of their index.
+fn spawn_documents<P>(processor: P, content: String, sender: Sender<Vec<Value>>) -> JoinHandle<()>
+where
+    P: LogProcessor + Send + 'static,
+    P::Out: IntoIterator,
+    <P::Out as IntoIterator>::Item: Serialize,
+{
+    tokio::spawn(async move {
+        let documents = processor
+            .parse(&content)
+            .into_iter()
+            .filter_map(|item| serde_json::to_value(item).ok())
+            .collect::<Vec<_>>();
+
+        let _ = sender.send(documents).await;
+    })
+}
+
+/// Indexes every batch sent for `index`, after the privacy policy when one
+/// applies.
+fn spawn_receiver(
+    elastic: Arc<ElasticsearchClient>,
+    privacy: Option<Arc<PrivacyPolicy>>,
+    index: &'static str,
+    mut receiver: Receiver<Vec<Value>>,
+) -> JoinHandle<()> {
+    tokio::task::spawn(async move {
+        while let Some(mut data) = receiver.recv().await {
+            if let Some(privacy) = &privacy {
+                data.iter_mut().for_each(|document| privacy.apply(document));
+            }
+            elastic.insert_many(index, data).await;
+        }
+    })
+}
+
+/// Exposure lookups: `lookup <email|password> [value]` (the value is read
+/// from stdin when omitted), `range <email|password> <prefix>` and
+/// `serve [address]`.
+async fn run_command(
+    args: &[String],
+    settings: &Settings,
+    elastic: Arc<ElasticsearchClient>,
+    hasher: Option<Arc<ExposureHasher>>,
+) -> tokio::io::Result<()> {
+    let usage = || {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "usage: lookup <email|password> [value] | range <email|password> <prefix> | serve [address]",
+        )
+    };
+    let kind = args.get(1).and_then(|name| ExposureKind::from_name(name));
+
+    if !matches!((args[0].as_str(), kind), ("lookup" | "range", Some(_)) | ("serve", _)) {
+        return Err(usage());
+    }
+
+    let hasher = hasher.ok_or(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        "Exposure lookups need an exposure key or algorithm",
+    ))?;
+    let lookup = ExposureLookup::new(elastic, hasher);
+
+    match (args[0].as_str(), kind) {
+        ("lookup", Some(kind)) => {
+            let value = match args.get(2) {
+                Some(value) => value.clone(),
+                None => {
+                    let mut line = String::new();
+                    std::io::stdin().read_line(&mut line)?;
+                    line.trim_end_matches(['\r', '\n']).to_string()
+                }
+            };
+
+            println!("{}", lookup.check(kind, &value).await?);
+            Ok(())
+        }
+        ("range", Some(kind)) => {
+            let prefix = args.get(2).ok_or_else(usage)?;
+
+            print!("{}", format_range(&lookup.range(kind, prefix).await?));
+            Ok(())
+        }
+        ("serve", _) => {
+            let address = args
+                .get(1)
+                .cloned()
+                .or(settings.exposure.listen.clone())
+                .unwrap_or(String::from(DEFAULT_LISTEN));
+
+            ExposureServer::new(lookup).listen(&address).await
+        }
+        _ => Err(usage()),
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -252,4 +905,754 @@ mod tests {
         let verify = ArchiveUtils::is_registered(&hash).unwrap();
         println!("{} - {}", hash, verify);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn normalize_country_spellings() {
+        use crate::log_processor::country::to_alpha2;
+
+        for spelling in ["US", "USA", "United States", "Estados Unidos", "u.s.a"] {
+            assert_eq!(to_alpha2(spelling), Some("US"));
+        }
+        assert_eq!(to_alpha2("Deutschland"), Some("DE"));
+        assert_eq!(to_alpha2("Korea, Republic of"), Some("KR"));
+        assert_eq!(to_alpha2("BR (Brazil)"), Some("BR"));
+        assert_eq!(to_alpha2("Atlantis"), None);
+    }
+
+    #[test]
+    fn skip_geoip_enrichment() {
+        use crate::config::GeoIpSettings;
+
+        let enricher = GeoIpEnricher::new(&GeoIpSettings::default()).unwrap();
+        assert!(!enricher.is_enabled());
+        assert!(enricher.lookup("8.8.8.8".parse().unwrap()).is_none());
+
+        for content in ["IP: 8.8.8.8", "IP: not-an-ip", "IP: ", "HWID: ABC123"] {
+            let info = InfoLogProcessor::new().parse(content);
+            let before = serde_json::to_value(&info).unwrap();
+            let info = enricher.enrich(info);
+
+            assert!(info.geo().is_none());
+            assert_eq!(serde_json::to_value(&info).unwrap(), before);
+        }
+
+        let missing = GeoIpSettings { city_database: Some(String::from("missing/GeoLite2-City.mmdb")), asn_database: None };
+        assert!(GeoIpEnricher::new(&missing).is_err());
+    }
+
+    #[test]
+    fn parse_info_sections() {
+        let content = "Network Info:\n\t- IP: 10.0.0.1\n\t- Country: Brasil\nSystem Summary:\n\t- HWID: ABC123\nInstalled Apps:\n\tAll Users:\n\t\t7-Zip 19.00 (x64)\n\t\tESET Security\nProcess List:\n\t- chrome.exe\n\t- avp.exe\n[Hardware]\n\t1) Intel(R) Core(TM) i5\n";
+        let info = InfoLogProcessor::new().parse(content);
+
+        assert_eq!(info.ip().as_deref(), Some("10.0.0.1"));
+        assert_eq!(info.country().as_deref(), Some("BR"));
+        assert_eq!(info.hwid().as_deref(), Some("ABC123"));
+        assert_eq!(info.installed_software(), ["7-Zip 19.00 (x64)", "ESET Security"]);
+        assert_eq!(info.processes(), ["chrome.exe", "avp.exe"]);
+        assert_eq!(info.hardware(), ["Intel(R) Core(TM) i5"]);
+
+        let info = InfoLogProcessor::new().parse("Installed Software:\n\t1) Chrome\nHWID: ABC\nCountry: US\nProcesses:\n\t- AVP.exe\n\t- avp.exe\n");
+        assert_eq!(info.installed_software(), ["Chrome"]);
+        assert_eq!(info.hwid().as_deref(), Some("ABC"));
+        assert_eq!(info.country().as_deref(), Some("US"));
+
+        let host = serde_json::to_value(HostDocument::new("archive", "US[1234]", &info)).unwrap();
+        assert_eq!(host["processes"], json!(["avp.exe"]));
+        assert_eq!(host["hwid"], "ABC");
+    }
+
+    #[test]
+    fn pick_infection_date() {
+        let info = InfoLogProcessor::new().parse("Log Date: 2024-05-02 10:00:00\nInstall Date: 2019-01-01 00:00:00\nLocal Time: 2023-01-01 00:00:00");
+        assert_eq!(info.infection_date().unwrap().to_rfc3339(), "2024-05-02T10:00:00+00:00");
+
+        let info = InfoLogProcessor::new().parse("Local Time: 05/02/2024 22:00:00");
+        assert_eq!(info.infection_date().unwrap().to_rfc3339(), "2024-05-02T22:00:00+00:00");
+
+        let info = InfoLogProcessor::new().parse("Date: 05/02/2024 10:00:00 PM");
+        assert_eq!(info.infection_date().unwrap().to_rfc3339(), "2024-05-02T22:00:00+00:00");
+
+        let info = InfoLogProcessor::new().parse("Date: 25/12/2023 08:00:00");
+        assert_eq!(info.infection_date().unwrap().to_rfc3339(), "2023-12-25T08:00:00+00:00");
+    }
+
+    #[test]
+    fn collect_iocs_across_logs() {
+        let content = "HWID: 0123456789ABCDEF0123456789ABCDEF\nBuild ID: campaign1\nPath: C:\\Users\\bob\\AppData\\Local\\Temp\\loader.exe\n\
+            File hash: D41D8CD98F00B204E9800998ECF8427E\n";
+        let info = InfoLogProcessor::new().parse(content);
+        let iocs = IocLogProcessor::new(&info).parse(content);
+        let values = serde_json::to_value(&iocs).unwrap();
+
+        assert!(values.as_array().unwrap().contains(&json!({"kind": "md5", "value": "d41d8cd98f00b204e9800998ecf8427e"})));
+        assert!(values.as_array().unwrap().contains(&json!({"kind": "malware_name", "value": "loader.exe"})));
+        assert!(values.as_array().unwrap().contains(&json!({"kind": "build_id", "value": "campaign1"})));
+        assert!(!values.to_string().contains("0123456789abcdef0123456789abcdef"));
+
+        let content = "MachineID: 3F2504E04F8941D39A0C0305E82C3301\nGUID: 9c5b94b1-35ad-49bb-b118-8e8fc24abf80\n\
+            Session: 2fd4e1c67a2d28fced849ee1bb76e7391b93eb12\nSHA256: E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855\n";
+        let labelled = IocLogProcessor::new(&InfoLogProcessor::new().parse(content)).parse(content);
+        assert_eq!(
+            serde_json::to_value(&labelled).unwrap(),
+            json!([{"kind": "sha256", "value": "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"}])
+        );
+
+        let mut collector = IocCollector::new("archive");
+        collector.add("US[1]", iocs.clone());
+        collector.add("US[2]", iocs.clone());
+        collector.add("US[2]", iocs.clone());
+        let documents = serde_json::to_value(collector.documents()).unwrap();
+
+        assert_eq!(documents.as_array().unwrap().len(), iocs.len());
+        assert!(documents.as_array().unwrap().iter().all(|document| document["log_count"] == 2));
+        assert_eq!(documents[0]["logs"], json!(["US[1]", "US[2]"]));
+    }
+
+    #[test]
+    fn export_stix_bundle() {
+        use crate::config::StixSettings;
+        use crate::export::{stix::StixExporter, ArchiveIntel};
+
+        let mut intel = ArchiveIntel::new("archive");
+        intel.add(InfoLogProcessor::new().parse("Stealer: RedLine\nBuild ID: campaign1\nPath: C:\\Users\\bob\\AppData\\Local\\Temp\\loader.exe\n\
+            Log Date: 2024-01-10 10:00:00\nCountry: US\nHWID: ABCDEF123456\nIP: 203.0.113.42"));
+        intel.add(InfoLogProcessor::new().parse("Stealer: RedLine\nPath: C:\\Users\\eve\\AppData\\Local\\Temp\\loader.exe\nLog Date: 2024-03-01 10:00:00\nCountry: DE"));
+        intel.add(InfoLogProcessor::new().parse("Path: C:\\Users\\joe\\unknown.exe\nLog Date: 2024-01-12 10:00:00"));
+
+        let bundle = StixExporter::new(&StixSettings::default()).bundle(&intel);
+        let objects = bundle["objects"].as_array().unwrap();
+        let of_type = |kind: &str| objects.iter().filter(|object| object["type"] == kind).collect::<Vec<_>>();
+
+        assert_eq!(of_type("malware").len(), 1);
+        assert_eq!(of_type("malware")[0]["name"], "RedLine");
+        assert_eq!(of_type("malware")[0]["x_build_ids"], json!(["campaign1"]));
+        assert_eq!(of_type("indicator").len(), 1);
+        assert_eq!(of_type("indicator")[0]["pattern"], "[file:name = 'loader.exe']");
+        assert_eq!(of_type("relationship")[0]["source_ref"], of_type("indicator")[0]["id"]);
+        assert_eq!(of_type("relationship")[0]["target_ref"], of_type("malware")[0]["id"]);
+        assert_eq!(of_type("sighting")[0]["count"], 2);
+        assert_eq!(of_type("location").len(), 2);
+        assert_eq!(of_type("directory")[0]["id"], "directory--708cbbcd-252a-58ec-8e73-3cb89f443778");
+        assert_eq!(of_type("file")[0]["parent_directory_ref"], of_type("directory")[0]["id"]);
+        assert_eq!(of_type("observed-data")[0]["object_refs"], json!([of_type("directory")[0]["id"], of_type("file")[0]["id"]]));
+
+        let text = bundle.to_string();
+        assert!(!text.contains("unknown.exe"));
+        assert!(!text.contains("ABCDEF123456"));
+        assert!(!text.contains("203.0.113.42"));
+        assert!(!text.contains("bob") && !text.contains("eve"));
+
+        let settings = StixSettings {
+            date_from: chrono::NaiveDate::from_ymd_opt(2024, 1, 1),
+            date_to: chrono::NaiveDate::from_ymd_opt(2024, 1, 31),
+            ..Default::default()
+        };
+        let bundle = StixExporter::new(&settings).bundle(&intel);
+        let sightings = bundle["objects"].as_array().unwrap().iter().filter(|object| object["type"] == "sighting").collect::<Vec<_>>();
+
+        assert_eq!(sightings.len(), 1);
+        assert_eq!(sightings[0]["count"], 1);
+        assert_eq!(sightings[0]["first_seen"], "2024-01-10T10:00:00.000Z");
+    }
+
+    #[test]
+    fn export_misp_event() {
+        use crate::config::MispSettings;
+        use crate::export::{misp::MispExporter, ArchiveIntel};
+
+        let mut intel = ArchiveIntel::new("archive");
+        intel.add(InfoLogProcessor::new().parse("Stealer: LummaC2\nBuild ID: campaign1\nPath: C:\\Users\\bob\\AppData\\Roaming\\svc.exe\n\
+            Country: US\nHWID: ABCDEF123456\nIP: 203.0.113.42"));
+        intel.add(InfoLogProcessor::new().parse("Stealer: LummaC2\nBuild ID: campaign1\nCountry: US"));
+        intel.add_domains([
+            (String::from("corp.example"), 3),
+            (String::from("mail.corp.example"), 1),
+            (String::from("gmail.com"), 5),
+        ]);
+
+        let exporter = MispExporter::new(
+            &MispSettings { tlp: Some(String::from("GREEN")), tags: vec![String::from("source:logs")] },
+            &[String::from("Corp.example")],
+        );
+        let event = exporter.event(&intel);
+        let tags = event["Event"]["Tag"].as_array().unwrap().iter().map(|tag| tag["name"].as_str().unwrap()).collect::<Vec<_>>();
+
+        assert_eq!(tags, ["tlp:green", "misp-galaxy:stealer=\"LummaC2\"", "source:logs"]);
+
+        let attributes = event["Event"]["Attribute"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|attribute| {
+                (
+                    attribute["category"].as_str().unwrap().to_string(),
+                    attribute["type"].as_str().unwrap().to_string(),
+                    attribute["value"].as_str().unwrap().to_string(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let attribute = |category: &str, kind: &str, value: &str| (category.to_string(), kind.to_string(), value.to_string());
+
+        assert_eq!(
+            attributes,
+            [
+                attribute("Attribution", "text", "LummaC2"),
+                attribute("Attribution", "campaign-id", "campaign1"),
+                attribute("Payload installation", "filename", "C:\\Users\\*\\AppData\\Roaming\\svc.exe"),
+                attribute("Targeting data", "target-location", "US"),
+                attribute("Network activity", "domain", "corp.example"),
+                attribute("Network activity", "domain", "mail.corp.example"),
+            ]
+        );
+
+        let again = exporter.event(&intel);
+        assert_eq!(event["Event"]["uuid"], again["Event"]["uuid"]);
+        assert_eq!(event["Event"]["Attribute"], again["Event"]["Attribute"]);
+
+        let text = event.to_string();
+        assert!(!text.contains("ABCDEF123456"));
+        assert!(!text.contains("203.0.113.42"));
+        assert!(!text.contains("bob"));
+    }
+
+    #[test]
+    fn parse_password_blocks_in_order() {
+        let content = "URL: https://a.example/login\nUsername: alice\nPassword: p=ss:1\n===============\nURL: https://b.example\nUsername: bob\nPassword: hunter2\n\nURL: https://c.example\nUsername: carol\nPassword: last\n";
+        let processor = PassLogProcessor::new(&InfoLogProcessor::new().parse(""), &CredentialOptions::default());
+
+        for _ in 0..3 {
+            let credentials = processor
+                .parse(content)
+                .iter()
+                .map(|credential| (credential.url().unwrap(), credential.username().unwrap(), credential.password().unwrap()))
+                .collect::<Vec<_>>();
+
+            assert_eq!(
+                credentials,
+                [
+                    (String::from("https://a.example/login"), String::from("alice"), String::from("p=ss:1")),
+                    (String::from("https://b.example"), String::from("bob"), String::from("hunter2")),
+                    (String::from("https://c.example"), String::from("carol"), String::from("last")),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn parse_password_aliases_and_combo_lines() {
+        let content = "Soft: Google Chrome [Profile 1]\nHost: https://a.example\nLogin: alice\nPassword: one\n\nhttps://b.example:8443/login:bob:two:three\nc.example|carol|four\n";
+        let processor = PassLogProcessor::new(&InfoLogProcessor::new().parse(""), &CredentialOptions::default());
+        let credentials = processor.parse(content);
+
+        assert_eq!(credentials.len(), 3);
+        assert_eq!(credentials[0].application().as_deref(), Some("Google Chrome"));
+        assert_eq!(credentials[0].profile().as_deref(), Some("Profile 1"));
+        assert_eq!(credentials[0].username().as_deref(), Some("alice"));
+        assert_eq!(credentials[1].url().as_deref(), Some("https://b.example:8443/login"));
+        assert_eq!(credentials[1].password().as_deref(), Some("two:three"));
+        assert_eq!(credentials[2].username().as_deref(), Some("carol"));
+
+        let content = "Comment: a;b;c\nNote: x|y|z\nbrowser: Google Chrome\nprofile: Default\nurl: https://d.example\nlogin: dave\npassword: five\n";
+        let credentials = processor.parse(content);
+
+        assert_eq!(credentials.len(), 1);
+        assert_eq!(credentials[0].application().as_deref(), Some("Google Chrome"));
+        assert_eq!(credentials[0].profile().as_deref(), Some("Default"));
+    }
+
+    #[test]
+    fn decompose_credential_urls() {
+        use crate::log_processor::credential_url::UrlParts;
+
+        let parts = UrlParts::parse("https://login.corp.example.co.uk:8443/auth?next=1").unwrap();
+        assert_eq!(parts.host().as_deref(), Some("login.corp.example.co.uk"));
+        assert_eq!(parts.domain().as_deref(), Some("example.co.uk"));
+
+        let bare = UrlParts::parse("accounts.example.com/signin").unwrap();
+        assert_eq!(bare.domain().as_deref(), Some("example.com"));
+
+        let android = UrlParts::parse("android://aGFzaA==@com.example.app/").unwrap();
+        assert_eq!(android.host(), None);
+        assert_eq!(android.domain(), None);
+    }
+
+    #[test]
+    fn map_android_credentials_to_owner() {
+        use crate::config::CredentialSettings;
+        use crate::log_processor::pass_log_processor::CredentialOrigin;
+
+        let mut settings = CredentialSettings::default();
+        settings.android_owners.insert(String::from("com.example.app"), String::from("example.com"));
+
+        let content = "URL: android://aGFzaA==@com.example.app/\nUsername: alice\nPassword: one\n";
+        let processor = PassLogProcessor::new(&InfoLogProcessor::new().parse(""), &CredentialOptions::new(&settings));
+        let credentials = processor.parse(content);
+
+        assert_eq!(credentials[0].origin(), CredentialOrigin::MobileApp);
+        assert_eq!(credentials[0].domain().as_deref(), Some("example.com"));
+    }
+
+    #[test]
+    fn classify_credential_usernames() {
+        use crate::log_processor::identity::{Identity, IdentityKind};
+
+        let email = Identity::classify("  John.Doe@Bücher.Example ").unwrap();
+        assert_eq!(email.kind(), IdentityKind::Email);
+        assert_eq!(email.normalized(), "john.doe@xn--bcher-kva.example");
+        assert_eq!(email.email_domain().as_deref(), Some("xn--bcher-kva.example"));
+
+        let phone = Identity::classify("+55 (11) 91234-5678").unwrap();
+        assert_eq!(phone.kind(), IdentityKind::Phone);
+        assert_eq!(phone.normalized(), "+5511912345678");
+
+        assert_eq!(Identity::classify("1234").unwrap().kind(), IdentityKind::Login);
+        assert_eq!(Identity::classify("(555) 123-4567").unwrap().kind(), IdentityKind::Phone);
+        for login in ["2024-01-01", "01/02/2024", "00123456", "12345678901", "192.168.100.200"] {
+            assert_eq!(Identity::classify(login).unwrap().kind(), IdentityKind::Login, "{}", login);
+        }
+        assert_eq!(Identity::classify("gamer_01").unwrap().kind(), IdentityKind::Login);
+    }
+
+    #[test]
+    fn apply_privacy_policy() {
+        use crate::config::{PrivacyAction, PrivacySettings};
+
+        let settings = PrivacySettings {
+            name: Some(String::from("minimal")),
+            key: Some(String::from("secret")),
+            password: PrivacyAction::Hmac,
+            username: PrivacyAction::Mask,
+            cookie_value: PrivacyAction::Drop,
+            ip: PrivacyAction::Mask,
+            hwid: PrivacyAction::Drop,
+            autofill_value: PrivacyAction::Mask,
+        };
+        let policy = PrivacyPolicy::new(&settings).unwrap();
+
+        let mut credential = json!({
+            "username": "alice",
+            "password": "hunter2",
+            "identity": {"normalized": "alice"},
+            "infos": {"ip": "203.0.113.42", "hwid": "ABCD"}
+        });
+        policy.apply(&mut credential);
+
+        assert_eq!(credential["username"], "a***e");
+        assert_eq!(credential["identity"]["normalized"], "a***e");
+        assert_eq!(credential["password"].as_str().unwrap().len(), 64);
+        assert_ne!(credential["password"], "hunter2");
+        assert_eq!(credential["infos"]["ip"], "203.0.113.0");
+        assert!(credential["infos"].get("hwid").is_none());
+        assert_eq!(credential["privacy_policy"], "minimal");
+
+        let mut cookies = json!({"cookies": [{"name": "sid", "value": "abc"}]});
+        policy.apply(&mut cookies);
+        assert!(cookies["cookies"][0].get("value").is_none());
+
+        let settings = PrivacySettings { password: PrivacyAction::Hmac, ..Default::default() };
+        assert!(PrivacyPolicy::new(&settings).is_err());
+
+        let mut credential = json!({"username": "alice", "password": "hunter2", "cookies": [{"name": "sid", "value": "abc"}]});
+        PrivacyPolicy::new(&PrivacySettings::default()).unwrap().apply(&mut credential);
+        assert_eq!(credential["username"], "alice");
+        assert!(credential.get("password").is_none());
+        assert!(credential["cookies"][0].get("value").is_none());
+    }
+
+    #[test]
+    fn compute_password_metadata() {
+        let content = "URL: https://a.example.com\nUsername: bob\nPassword: Summer2024!\n\n\
+            URL: https://b.example.org\nUsername: bob\nPassword: Summer2024!\n\n\
+            URL: https://c.example.net\nUsername: bob\nPassword: bob\n";
+        let processor = PassLogProcessor::new(&InfoLogProcessor::new().parse("HWID: 1234"), &CredentialOptions::default());
+        let credentials = processor
+            .parse(content)
+            .iter()
+            .map(|credential| serde_json::to_value(credential).unwrap()["password_metadata"].clone())
+            .collect::<Vec<_>>();
+
+        assert_eq!(credentials[0]["length"], 11);
+        assert_eq!(credentials[0]["class_count"], 4);
+        assert_eq!(credentials[0]["reused"], true);
+        assert_eq!(credentials[0]["reuse_count"], 2);
+        assert_eq!(credentials[0]["reuse_cluster"], credentials[1]["reuse_cluster"]);
+        assert_eq!(credentials[2]["equals_username"], true);
+        assert_eq!(credentials[2]["reused"], false);
+        assert!(credentials[2]["reuse_cluster"].is_null());
+
+        let content = "URL: https://a.example.com\nUsername: bob\nPassword: Summer2024!\n\n\
+            URL: https://b.example.org\nUsername: bob\nPassword: Summer2024!\n\n\
+            URL: https://a.example.com\nUsername: alice\nPassword: Winter2023?\n\n\
+            URL: https://b.example.org\nUsername: alice\nPassword: Winter2023?\n";
+        let clusters = processor
+            .parse(content)
+            .iter()
+            .map(|credential| serde_json::to_value(credential).unwrap()["password_metadata"]["reuse_cluster"].clone())
+            .collect::<Vec<_>>();
+
+        assert_eq!(clusters[0], clusters[1]);
+        assert_eq!(clusters[2], clusters[3]);
+        assert!(clusters[0].is_string());
+        assert_ne!(clusters[0], clusters[2]);
+    }
+
+    #[test]
+    fn hash_exposure_records() {
+        use crate::config::{ExposureAlgorithm, ExposureSettings, PrivacyAction, PrivacySettings};
+
+        let keep = PrivacySettings { password: PrivacyAction::Keep, ..Default::default() };
+        let sha1 = ExposureSettings { algorithm: Some(ExposureAlgorithm::Sha1), ..Default::default() };
+        let hasher = ExposureHasher::new(&sha1, &keep).unwrap().unwrap();
+        assert_eq!(hasher.hash("password"), "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8");
+
+        let credential = PassLogProcessor::new(&InfoLogProcessor::new().parse(""), &CredentialOptions::default())
+            .parse("URL: https://example.com\nLogin:  Alice@Example.COM\nPassword: password\n")
+            .remove(0);
+        let records = serde_json::to_value(hasher.records(&credential)).unwrap();
+
+        assert_eq!(records[0]["kind"], "email");
+        assert_eq!(records[0]["prefix"], &hasher.hash("alice@example.com")[..5]);
+        assert_eq!(records[1]["kind"], "password");
+        assert_eq!(records[1]["prefix"], "5BAA6");
+        assert_eq!(records[1]["suffix"], "1E4C9B93F3F0682250B6CF8331B7EE68FD8");
+
+        // Passwords dropped by the privacy policy get no record either.
+        let keyed = ExposureSettings { key: Some(String::from("secret")), ..Default::default() };
+        let hasher = ExposureHasher::new(&keyed, &PrivacySettings::default()).unwrap().unwrap();
+        let records = serde_json::to_value(hasher.records(&credential)).unwrap();
+        assert_eq!(records.as_array().unwrap().len(), 1);
+        assert_eq!(records[0]["kind"], "email");
+        assert_ne!(hasher.hash("alice@example.com"), ExposureHasher::new(&sha1, &keep).unwrap().unwrap().hash("alice@example.com"));
+
+        assert!(ExposureHasher::new(&ExposureSettings::default(), &keep).unwrap().is_none());
+        let settings = ExposureSettings { algorithm: Some(ExposureAlgorithm::Hmac), ..Default::default() };
+        assert!(ExposureHasher::new(&settings, &keep).is_err());
+        assert!(exposure::parse_prefix("5baa6").is_ok());
+        assert!(exposure::parse_prefix("5BAA").is_err());
+    }
+
+    #[test]
+    fn match_directory_accounts() {
+        use crate::directory::{DirectoryExport, DirectoryReport};
+
+        let path = std::env::temp_dir().join("log-archive-processor-directory.txt");
+        std::fs::write(
+            &path,
+            "CORP\\jdoe:1104:aad3b435b51404eeaad3b435b51404ee:8846f7eaee8fb117ad06bdd830b7586c:::\n\
+             asmith:31d6cfe0d16ae931b73c59d7e0c089c0\n\
+             broken line\n",
+        )
+        .unwrap();
+        let export = DirectoryExport::load(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(export.len(), 2);
+
+        let checker = DirectoryChecker::with_export(export, &[String::from("corp.com")]);
+        let info = InfoLogProcessor::new().parse("HWID: 1234");
+        let credentials = PassLogProcessor::new(&info, &CredentialOptions::default()).parse(
+            "URL: https://mail.corp.com\nLogin: JDoe@corp.com\nPassword: password\n\n\
+             URL: https://other.org\nLogin: jdoe\nPassword: password\n\n\
+             URL: https://vpn.corp.com\nLogin: asmith\nPassword: wrong\n",
+        );
+
+        let mut report = DirectoryReport::default();
+        credentials
+            .iter()
+            .chain(credentials.iter())
+            .filter_map(|credential| checker.check(credential, &info))
+            .for_each(|found| report.add(found));
+        let other_log = InfoLogProcessor::new().parse("HWID: 5678");
+        credentials
+            .iter()
+            .filter_map(|credential| checker.check(credential, &other_log))
+            .for_each(|found| report.add(found));
+        let matches = serde_json::to_value(report.matches()).unwrap();
+
+        assert_eq!(matches.as_array().unwrap().len(), 1);
+        assert_eq!(matches[0]["account"], "jdoe");
+        assert_eq!(matches[0]["hash_type"], "ntlm");
+        assert_eq!(matches[0]["sightings"].as_array().unwrap().len(), 2);
+        assert_eq!(matches[0]["sightings"][0]["domain"], "corp.com");
+        assert!(!matches.to_string().contains("password"));
+    }
+
+    #[test]
+    fn parse_netscape_cookies_by_position() {
+        use crate::log_processor::cook_log_processor::parse_netscape;
+
+        let content = "# Netscape HTTP Cookie File\n\
+            .example.com\tTRUE\t/\tFALSE\t1700000000\tsid\t\n\
+            #HttpOnly_example.com\tFALSE\t\tTRUE\t0\ttoken\ta\tb\n\
+            example.com\tTRUE\t/\n\
+            \n";
+        let (cookies, malformed) = parse_netscape(content);
+        let cookies = serde_json::to_value(cookies).unwrap();
+
+        assert_eq!(malformed, vec![4]);
+        assert_eq!(cookies[0]["domain"], ".example.com");
+        assert_eq!(cookies[0]["include_subdomains"], true);
+        assert_eq!(cookies[0]["name"], "sid");
+        assert_eq!(cookies[0]["value"], "");
+        assert_eq!(cookies[1]["domain"], "example.com");
+        assert_eq!(cookies[1]["http_only"], true);
+        assert_eq!(cookies[1]["path"], "");
+        assert_eq!(cookies[1]["value"], "a\tb");
+    }
+
+    #[test]
+    fn detect_cookie_dump_formats() {
+        use crate::log_processor::cook_log_processor::{parse_cookies, CookieFormat};
+
+        let json = r#"[
+            {"domain": ".example.com", "hostOnly": false, "httpOnly": true, "name": "sid", "path": "/", "secure": true, "expirationDate": 1700000000.5, "value": "abc"},
+            {"value": "orphan"}
+        ]"#;
+        assert_eq!(CookieFormat::detect(json), CookieFormat::Json);
+
+        let (cookies, malformed) = parse_cookies(json);
+        let cookies = serde_json::to_value(cookies).unwrap();
+        assert_eq!(malformed, vec![2]);
+        assert_eq!(cookies[0]["include_subdomains"], true);
+        assert_eq!(cookies[0]["http_only"], true);
+        assert_eq!(cookies[0]["expires_at"], "2023-11-14T22:13:20Z");
+        assert_eq!(cookies[0]["value"], "abc");
+
+        let text = "sid=abc; domain=example.com; path=/; secure; HttpOnly\ntoken=xyz; path=/\n";
+        assert_eq!(CookieFormat::detect(text), CookieFormat::Semicolon);
+
+        let (cookies, malformed) = parse_cookies(text);
+        let cookies = serde_json::to_value(cookies).unwrap();
+        assert_eq!(malformed, vec![2]);
+        assert_eq!(cookies[0]["domain"], "example.com");
+        assert_eq!(cookies[0]["include_subdomains"], false);
+        assert_eq!(cookies[0]["secure"], true);
+        assert_eq!(cookies[0]["http_only"], true);
+
+        assert_eq!(CookieFormat::detect(".example.com\tTRUE\t/\tFALSE\t0\tsid\tabc"), CookieFormat::Netscape);
+    }
+
+    #[test]
+    fn type_cookie_expiry() {
+        use crate::log_processor::cook_log_processor::{parse_expiry, CookieLogProcessor};
+
+        let expected = parse_expiry("2023-11-14T22:13:20Z");
+        assert!(expected.is_some());
+        assert_eq!(parse_expiry("1700000000"), expected);
+        assert_eq!(parse_expiry("1700000000000"), expected);
+        assert_eq!(parse_expiry("13344473600000000"), expected);
+        assert_eq!(parse_expiry("Tue, 14 Nov 2023 22:13:20 GMT"), expected);
+        assert_eq!(parse_expiry("Tue, 14-Nov-2023 22:13:20 GMT"), expected);
+        assert_eq!(parse_expiry("0"), None);
+        assert_eq!(parse_expiry("253402300799").map(|date| date.to_rfc3339()).as_deref(), Some("9999-12-31T23:59:59+00:00"));
+
+        let info = InfoLogProcessor::new().parse("Log Date: 2024-01-01 10:00:00");
+        let content = "example.com\tFALSE\t/\tFALSE\t1700000000\texpired\ta\n\
+            example.com\tFALSE\t/\tFALSE\t1800000000\tlive\tb\n\
+            example.com\tFALSE\t/\tFALSE\t0\tsession\tc\n";
+        let documents = CookieLogProcessor::new(&info, CookieSource::default(), &CookieOptions::default()).parse(content).unwrap();
+        let cookies = serde_json::to_value(&documents["example.com"]).unwrap()["cookies"].clone();
+
+        assert_eq!(cookies[0]["valid_at_infection"], false);
+        assert_eq!(cookies[0]["session"], false);
+        assert_eq!(cookies[1]["valid_at_infection"], true);
+        assert_eq!(cookies[2]["session"], true);
+        assert!(cookies[2]["expires_at"].is_null());
+        assert_eq!(cookies[2]["valid_at_infection"], true);
+    }
+
+    #[test]
+    fn attach_cookie_provenance() {
+        let info = InfoLogProcessor::new().parse("HWID: ABCD");
+        let content = "example.com\tFALSE\t/\tFALSE\t0\tsid\ta\n";
+
+        let sources = [
+            ("US[1234]/Cookies/Google Chrome_Default.txt", Some("Google Chrome"), Some("Default")),
+            ("US[1234]/Cookies/Edge [Profile 1].txt", Some("Edge"), Some("Profile 1")),
+            ("US[1234]/Cookies/Google_[Chrome]_Profile 2.txt", Some("Google Chrome"), Some("Profile 2")),
+            ("US[1234]/Browsers/Firefox/abcd.default/Cookies.txt", Some("Firefox"), Some("abcd.default")),
+            ("US[1234]/Cookies.txt", None, None),
+        ];
+
+        for (path, browser, profile) in sources {
+            let source = CookieSource::new("archivehash", "US[1234]", path);
+            let documents = CookieLogProcessor::new(&info, source, &CookieOptions::default()).parse(content).unwrap();
+            let document = serde_json::to_value(&documents["example.com"]).unwrap();
+
+            assert_eq!(document["hwid"], "ABCD");
+            assert_eq!(document["archive"], "archivehash");
+            assert_eq!(document["log_folder"], "US[1234]");
+            assert_eq!(document["source_path"], path);
+            assert_eq!(document["browser"].as_str(), browser, "{}", path);
+            assert_eq!(document["profile"].as_str(), profile, "{}", path);
+        }
+    }
+
+    #[test]
+    fn index_cookie_metadata_only() {
+        assert!(CookieOptions::new(CookieMode::Metadata, None).is_err());
+
+        let options = CookieOptions::new(CookieMode::Metadata, Some("secret")).unwrap();
+        let info = InfoLogProcessor::new().parse("");
+        let content = "example.com\tFALSE\t/\tTRUE\t1800000000\tsid\tsecret-session\n\
+            example.org\tFALSE\t/\tTRUE\t1800000000\tsid\tsecret-session\n";
+        let documents = CookieLogProcessor::new(&info, CookieSource::default(), &options).parse(content).unwrap();
+
+        let com = serde_json::to_value(&documents["example.com"]).unwrap()["cookies"][0].clone();
+        let org = serde_json::to_value(&documents["example.org"]).unwrap()["cookies"][0].clone();
+
+        assert!(com["value"].is_null());
+        assert_eq!(com["name"], "sid");
+        assert_eq!(com["secure"], true);
+        assert_eq!(com["value_hash"].as_str().unwrap().len(), 64);
+        assert_eq!(com["value_hash"], org["value_hash"]);
+        assert!(!com.to_string().contains("secret-session"));
+    }
+
+    #[test]
+    fn score_session_exposure() {
+        use crate::config::AuthCookieService;
+
+        let catalogue = SessionCatalogue::new(&[AuthCookieService {
+            service: String::from("Example"),
+            domains: vec![String::from("example.com")],
+            cookies: vec![String::from("sid"), String::from("auth_*")],
+        }]);
+        let info = InfoLogProcessor::new().parse("HWID: ABCD\nLog Date: 2024-01-01 10:00:00");
+        let content = ".example.com\tTRUE\t/\tTRUE\t1800000000\tsid\tsecret\n\
+            login.example.com\tFALSE\t/\tTRUE\t1600000000\tauth_token\tsecret\n\
+            login.example.com\tFALSE\t/\tTRUE\t1800000000\tauth_session\tsecret\n\
+            example.org\tFALSE\t/\tTRUE\t1800000000\tsid\tsecret\n";
+        let documents = CookieLogProcessor::new(&info, CookieSource::default(), &CookieOptions::default()).parse(content).unwrap();
+
+        let mut sessions = SessionCollector::default();
+        sessions.add("archive", "US[1234]", &info, catalogue.score(documents.values()));
+        let logs = serde_json::to_value(sessions.documents()).unwrap();
+
+        assert_eq!(logs.as_array().unwrap().len(), 1);
+        assert_eq!(logs[0]["hwid"], "ABCD");
+        assert_eq!(logs[0]["score"], 100);
+        assert_eq!(logs[0]["services"][0]["domain"], "example.com");
+        assert_eq!(logs[0]["services"][0]["cookies"], json!(["auth_session", "auth_token", "sid"]));
+        assert_eq!(logs[0]["services"][0]["valid_cookies"], json!(["auth_session", "sid"]));
+        assert!(!logs.to_string().contains("secret"));
+        assert!(!logs.to_string().contains("auth_*"));
+    }
+
+    #[test]
+    fn aggregate_history_domains() {
+        let info = InfoLogProcessor::new().parse("HWID: ABCD");
+        let content = "URL: https://www.example.com/download?id=1\nTitle: Example\nVisits: 3\nLast visit: 2024-01-01 10:00:00\n\n\
+            URL: https://cdn.example.com/file\nVisits: 2\n\n\
+            https://cracked.example.org/keygen\tKeygen\t4\t1700000000\n\
+            https://cracked.example.org/other\n\
+            not a url\n";
+        let documents = HistoryLogProcessor::new(&info, "archive", "US[1234]").parse(content);
+        let documents = serde_json::to_value(documents).unwrap();
+
+        assert_eq!(documents.as_array().unwrap().len(), 2);
+        assert_eq!(documents[0]["domain"], "example.com");
+        assert_eq!(documents[0]["visit_count"], 5);
+        assert_eq!(documents[0]["last_visit"], "2024-01-01T10:00:00Z");
+        assert_eq!(documents[0]["hwid"], "ABCD");
+        assert_eq!(documents[1]["domain"], "example.org");
+        assert_eq!(documents[1]["visit_count"], 5);
+        assert_eq!(documents[1]["last_visit"], "2023-11-14T22:13:20Z");
+        assert!(!documents.to_string().contains("keygen"));
+    }
+
+    #[test]
+    fn classify_and_mask_autofill() {
+        use crate::config::PrivacySettings;
+
+        let info = InfoLogProcessor::new().parse("");
+        let content = "Name: email\nValue: John.Doe@Corp.com\n====\n\
+            Name: firstname\nValue: John\n\n\
+            phone\t+1 (555) 123-4567\n\
+            search\tcracked games\n";
+        let document = AutofillLogProcessor::new(&info, "archive", "US[1234]").parse(content).unwrap();
+        let mut document = serde_json::to_value(document).unwrap();
+
+        let kinds = document["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| (entry["field"].as_str().unwrap().to_string(), entry["kind"].as_str().unwrap().to_string()))
+            .collect::<Vec<_>>();
+        assert!(kinds.contains(&(String::from("email"), String::from("email"))));
+        assert!(kinds.contains(&(String::from("firstname"), String::from("name"))));
+        assert!(kinds.contains(&(String::from("phone"), String::from("phone"))));
+        assert!(kinds.contains(&(String::from("search"), String::from("other"))));
+        assert_eq!(document["email_domains"], json!(["corp.com"]));
+
+        PrivacyPolicy::new(&PrivacySettings::default()).unwrap().apply(&mut document);
+        assert!(!document.to_string().contains("John"));
+        assert!(!document.to_string().contains("cracked games"));
+        assert!(document.to_string().contains("corp.com"));
+    }
+
+    #[test]
+    fn inventory_installed_software() {
+        use crate::log_processor::software_log_processor::normalize_product;
+
+        assert_eq!(normalize_product("Mozilla Firefox (x64 en-US)"), "mozilla firefox");
+        assert_eq!(normalize_product("Mozilla Firefox 120.0.1"), "mozilla firefox");
+        assert_eq!(normalize_product("7-Zip 23.01 (x64)"), "7-zip");
+
+        let info = InfoLogProcessor::new().parse("HWID: ABCD\nInstalled Software:\n\t1) Google Chrome [119.0.6045.160]\n");
+        let content = "Installed Software:\n\
+            1) Google Chrome [119.0.6045.160]\n\
+            2) Notepad++ - 8.5.8\n\
+            Kaspersky Free\t21.3.10.391\tAO Kaspersky Lab\n\n\
+            Name: Adobe Photoshop 2023 (Cracked)\nVersion: 24.0\nPublisher: Adobe Inc.\n";
+        let documents = SoftwareLogProcessor::new(&info, "archive", "US[1234]").parse(content);
+        let documents = serde_json::to_value(documents).unwrap();
+        let find = |name: &str| {
+            documents
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|document| document["normalized_name"] == name)
+                .cloned()
+                .unwrap_or_else(|| panic!("{} missing from {}", name, documents))
+        };
+
+        assert_eq!(documents.as_array().unwrap().len(), 4);
+        assert_eq!(find("google chrome")["version"], "119.0.6045.160");
+        assert_eq!(find("notepad++")["version"], "8.5.8");
+        assert_eq!(find("kaspersky free")["publisher"], "AO Kaspersky Lab");
+        assert_eq!(find("adobe photoshop 2023")["version"], "24.0");
+        assert_eq!(find("adobe photoshop 2023")["hwid"], "ABCD");
+    }
+
+    #[test]
+    fn inventory_grabbed_files() {
+        use crate::archive::FileDigest;
+        use crate::log_processor::grabbed_files::{is_grabbed, GrabbedFileDocument};
+
+        let info = InfoLogProcessor::new().parse("HWID: ABCD");
+        let document = |path: &str| {
+            let digest = FileDigest { size: 42, sha256: String::from("ab12") };
+            serde_json::to_value(GrabbedFileDocument::new("archive", "US[1234]", path, &info, digest)).unwrap()
+        };
+
+        assert!(is_grabbed("US[1234]/Grabber/Desktop/notes.txt"));
+        assert!(is_grabbed("US[1234]/Files/wallet.dat"));
+        assert!(!is_grabbed("US[1234]/Passwords.txt"));
+
+        let report = document("US[1234]/Grabber/Desktop/Report.DOCX");
+        assert_eq!(report["path"], "Grabber/Desktop/Report.DOCX");
+        assert_eq!(report["file_name"], "Report.DOCX");
+        assert_eq!(report["extension"], "docx");
+        assert_eq!(report["size"], 42);
+        assert_eq!(report["sha256"], "ab12");
+        assert_eq!(report["hwid"], "ABCD");
+        assert_eq!(report["category"], "documents");
+
+        assert_eq!(document("US[1234]/Files/.ssh/id_rsa")["category"], "keys");
+        assert_eq!(document("US[1234]/Files/id_rsa")["extension"], Value::Null);
+        assert_eq!(document("US[1234]/Files/wallet.dat")["category"], "keys");
+        assert_eq!(document("US[1234]/Files/vpn.ovpn")["category"], "configs");
+        assert_eq!(document("US[1234]/Files/photo.jpg")["category"], "other");
+    }
+}
@@ -0,0 +1,191 @@
+use std::{
+    io::{Error, ErrorKind, Result},
+    net::IpAddr,
+};
+
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::Sha256;
+
+use crate::config::{PrivacyAction, PrivacySettings};
+
+const DEFAULT_POLICY: &str = "default";
+const POLICY_FIELD: &str = "privacy_policy";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SensitiveField {
+    Password,
+    Username,
+    CookieValue,
+    Ip,
+    Hwid,
+    AutofillValue,
+}
+
+impl SensitiveField {
+    const ALL: [SensitiveField; 6] = [
+        SensitiveField::Password,
+        SensitiveField::Username,
+        SensitiveField::CookieValue,
+        SensitiveField::Ip,
+        SensitiveField::Hwid,
+        SensitiveField::AutofillValue,
+    ];
+
+    /// Dotted paths of the field in the indexed documents; arrays are walked
+    /// element by element.
+    fn paths(&self) -> &'static [&'static str] {
+        match self {
+            SensitiveField::Password => &["password"],
+            SensitiveField::Username => &["username", "identity.normalized"],
+            SensitiveField::CookieValue => &["cookies.value"],
+            SensitiveField::Ip => &["ip", "infos.ip"],
+            SensitiveField::Hwid => &["hwid", "infos.hwid"],
+            SensitiveField::AutofillValue => &["entries.value"],
+        }
+    }
+}
+
+/// Hex HMAC-SHA256 of the value.
+pub fn keyed_hash<V: AsRef<[u8]>>(key: &[u8], value: V) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("hmac accepts any key length");
+    mac.update(value.as_ref());
+
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn mask(value: &str) -> String {
+    let chars = value.chars().collect::<Vec<_>>();
+
+    match chars.len() {
+        0 => String::new(),
+        1 | 2 => "*".repeat(chars.len()),
+        len => format!("{}{}{}", chars[0], "*".repeat(len - 2), chars[len - 1]),
+    }
+}
+
+/// IPs stay valid addresses so the `ip` mapping still accepts them: the host
+/// part is zeroed (/24 for IPv4, /48 for IPv6).
+fn mask_ip(value: &str) -> String {
+    match value.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => {
+            let [a, b, c, _] = ip.octets();
+            format!("{}.{}.{}.0", a, b, c)
+        }
+        Ok(IpAddr::V6(ip)) => {
+            let segments = ip.segments();
+            format!("{:x}:{:x}:{:x}::", segments[0], segments[1], segments[2])
+        }
+        Err(_) => mask(value),
+    }
+}
+
+fn apply_path(value: &mut Value, path: &[&str], transform: &dyn Fn(&str) -> Option<String>) {
+    match value {
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| apply_path(item, path, transform)),
+        Value::Object(object) => {
+            let Some((key, rest)) = path.split_first() else {
+                return;
+            };
+
+            if !rest.is_empty() {
+                if let Some(child) = object.get_mut(*key) {
+                    apply_path(child, rest, transform);
+                }
+                return;
+            }
+
+            let Some(Value::String(current)) = object.get(*key) else {
+                return;
+            };
+
+            match transform(current) {
+                Some(replaced) => object.insert(key.to_string(), Value::String(replaced)),
+                None => object.remove(*key),
+            };
+        }
+        _ => {}
+    }
+}
+
+/// Field-level minimisation applied to documents before they are indexed.
+pub struct PrivacyPolicy {
+    name: String,
+    key: Option<Vec<u8>>,
+    actions: Vec<(SensitiveField, PrivacyAction)>,
+}
+
+impl PrivacyPolicy {
+    pub fn new(settings: &PrivacySettings) -> Result<Self> {
+        let actions = SensitiveField::ALL
+            .iter()
+            .map(|field| {
+                let action = match field {
+                    SensitiveField::Password => settings.password,
+                    SensitiveField::Username => settings.username,
+                    SensitiveField::CookieValue => settings.cookie_value,
+                    SensitiveField::Ip => settings.ip,
+                    SensitiveField::Hwid => settings.hwid,
+                    SensitiveField::AutofillValue => settings.autofill_value,
+                };
+                (*field, action)
+            })
+            .filter(|(_, action)| *action != PrivacyAction::Keep)
+            .collect::<Vec<_>>();
+
+        let key = settings
+            .key
+            .as_ref()
+            .filter(|key| !key.is_empty())
+            .map(|key| key.as_bytes().to_vec());
+
+        if key.is_none() && actions.iter().any(|(_, action)| *action == PrivacyAction::Hmac) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Privacy policy uses hmac but no key is configured",
+            ));
+        }
+
+        Ok(Self {
+            name: settings.name.clone().unwrap_or(String::from(DEFAULT_POLICY)),
+            key,
+            actions,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Hex HMAC-SHA256 of the value, `None` when no key is configured.
+    pub fn keyed_hash<V: AsRef<[u8]>>(&self, value: V) -> Option<String> {
+        Some(keyed_hash(self.key.as_ref()?, value))
+    }
+
+    pub fn apply(&self, document: &mut Value) {
+        self.actions.iter().for_each(|(field, action)| {
+            let transform = |value: &str| match action {
+                PrivacyAction::Keep => Some(value.to_string()),
+                PrivacyAction::Mask if *field == SensitiveField::Ip => Some(mask_ip(value)),
+                PrivacyAction::Mask => Some(mask(value)),
+                PrivacyAction::Hmac => self.keyed_hash(value),
+                PrivacyAction::Drop => None,
+            };
+
+            field.paths().iter().for_each(|path| {
+                let path = path.split('.').collect::<Vec<_>>();
+                apply_path(document, &path, &transform);
+            });
+        });
+
+        if let Value::Object(object) = document {
+            object.insert(POLICY_FIELD.to_string(), Value::String(self.name.clone()));
+        }
+    }
+}